@@ -0,0 +1,365 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt::Display};
+
+use color_eyre::{Result, eyre::eyre};
+use serde::Serialize;
+use tracing::{Level, event};
+
+use crate::{
+    install::Side,
+    jar,
+    packs::{CurseRef, Pack, curse_files_by_fingerprint, sha1_hex},
+    platforms::ApiClient,
+};
+
+/**
+ * What to do with a mod that is in the pack more than once
+ */
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicatePolicy {
+    /// Keep the copy with the highest version
+    KeepNewest,
+    /// Keep the manifest entry, dropping jars shipped in overrides
+    #[default]
+    KeepManifest,
+    /// Refuse to write the pack
+    Error,
+}
+impl DuplicatePolicy {
+    /**
+     * Reads a `--duplicates` flag value
+     */
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "keep-newest" => Ok(Self::KeepNewest),
+            "keep-manifest" => Ok(Self::KeepManifest),
+            "error" => Ok(Self::Error),
+            other => Err(eyre!("Unknown duplicate policy {}", other)),
+        }
+    }
+}
+
+/**
+ * Every copy of one mod and which of them was kept
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct DuplicateGroup {
+    pub kept: String,
+    pub dropped: Vec<String>,
+    /// What the copies were matched by
+    pub matched_by: Vec<String>,
+}
+/**
+ * Outcome of [dedupe]
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct DedupeReport {
+    pub policy: DuplicatePolicy,
+    pub groups: Vec<DuplicateGroup>,
+}
+
+/**
+ * Where a copy of a mod lives in the pack
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Location {
+    Entry(usize),
+    /// Override folder (as named in an mrpack) and path inside it
+    Override(&'static str, String),
+}
+/**
+ * Everything a copy can be recognised by
+ */
+#[derive(Debug)]
+struct ModCopy {
+    location: Location,
+    label: String,
+    sha1: Option<String>,
+    curse_project: Option<u32>,
+    curse_file: Option<u32>,
+    modrinth_project: Option<String>,
+    mod_ids: Vec<String>,
+    version: Option<String>,
+    /// Whether the copy ends up in a client and in a server install
+    client: bool,
+    server: bool,
+}
+
+impl ModCopy {
+    /**
+     * Whether both copies can end up in the same install, a client override never meets a server override
+     */
+    fn shares_side(&self, other: &ModCopy) -> bool {
+        (self.client && other.client) || (self.server && other.server)
+    }
+    /**
+     * Reasons `self` and `other` are the same mod, empty if they are not
+     */
+    fn matches(&self, other: &ModCopy) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if self.sha1.is_some() && self.sha1 == other.sha1 {
+            reasons.push("sha1".to_string());
+        }
+        if self.curse_project.is_some() && self.curse_project == other.curse_project {
+            reasons.push("curseforge project".to_string());
+        }
+        if self.modrinth_project.is_some() && self.modrinth_project == other.modrinth_project {
+            reasons.push("modrinth project".to_string());
+        }
+        for id in self.mod_ids.iter().filter(|id| other.mod_ids.contains(id)) {
+            reasons.push(format!("mod id {}", id));
+        }
+        reasons
+    }
+}
+
+/**
+ * Compares version strings segment by segment, numeric segments by value
+ */
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |v: &str| -> Vec<String> {
+        v.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (split(a), split(b));
+    for (x, y) in a.iter().zip(b.iter()) {
+        let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/**
+ * Every copy of a mod in the pack, manifest entries first, `known` gives override jars their curseforge file
+ */
+fn copies(pack: &Pack, known: &BTreeMap<String, CurseRef>) -> Vec<ModCopy> {
+    let mut out = Vec::new();
+    for (index, entry) in pack.entries.iter().enumerate() {
+        let mods = entry
+            .contents
+            .as_deref()
+            .and_then(|c| jar::inspect(c).ok())
+            .unwrap_or_default();
+        out.push(ModCopy {
+            location: Location::Entry(index),
+            label: entry.label(),
+            sha1: entry.sha1.clone(),
+            curse_project: entry.curse.as_ref().map(|c| c.project_id),
+            curse_file: entry.curse.as_ref().map(|c| c.file_id),
+            modrinth_project: entry.modrinth.as_ref().map(|m| m.project_id.clone()),
+            version: entry
+                .version_number
+                .clone()
+                .or(mods.first().and_then(|m| m.version.clone())),
+            mod_ids: mods.into_iter().map(|m| m.mod_id).collect(),
+            client: Side::Client.wants(entry),
+            server: Side::Server.wants(entry),
+        });
+    }
+    for (folder, overrides, client, server) in [
+        ("overrides", &pack.overrides.common, true, true),
+        ("client-overrides", &pack.overrides.client, true, false),
+        ("server-overrides", &pack.overrides.server, false, true),
+    ] {
+        for (path, data) in overrides.iter().filter(|(p, _)| p.ends_with(".jar")) {
+            let mods = match jar::inspect(data) {
+                Ok(mods) => mods,
+                Err(e) => {
+                    event!(Level::DEBUG, "Could not inspect {}: {}", path, e);
+                    Vec::new()
+                }
+            };
+            let label = format!("{}/{}", folder, path);
+            let curse = known.get(&label);
+            out.push(ModCopy {
+                location: Location::Override(folder, path.clone()),
+                sha1: Some(sha1_hex(data)),
+                curse_project: curse.map(|c| c.project_id),
+                curse_file: curse.map(|c| c.file_id),
+                modrinth_project: None,
+                version: mods.first().and_then(|m| m.version.clone()),
+                mod_ids: mods.into_iter().map(|m| m.mod_id).collect(),
+                label,
+                client,
+                server,
+            });
+        }
+    }
+    out
+}
+
+/**
+ * Index of the copy `policy` keeps, copies are in [copies] order so manifest entries come first
+ */
+fn keep(group: &[&ModCopy], policy: DuplicatePolicy) -> usize {
+    match policy {
+        DuplicatePolicy::KeepNewest => {
+            let mut best = 0;
+            for (index, copy) in group.iter().enumerate().skip(1) {
+                let current = group[best];
+                let newer = match (&copy.version, &current.version) {
+                    (Some(a), Some(b)) if compare_versions(a, b) != Ordering::Equal => {
+                        compare_versions(a, b) == Ordering::Greater
+                    }
+                    _ => {
+                        copy.curse_project == current.curse_project
+                            && copy.curse_file > current.curse_file
+                    }
+                };
+                if newer {
+                    best = index;
+                }
+            }
+            best
+        }
+        DuplicatePolicy::KeepManifest | DuplicatePolicy::Error => 0,
+    }
+}
+
+/**
+ * Curseforge file of every jar in the pack's overrides, keyed `<override folder>/<path>` as [dedupe] takes them
+ *
+ * Lets an override jar match the manifest entry of the same mod in another version, a failed lookup is logged and
+ * leaves the jars unknown
+ */
+pub async fn identify_overrides(pack: &Pack, client: &ApiClient) -> BTreeMap<String, CurseRef> {
+    let jars: Vec<(String, &[u8])> = [
+        ("overrides", &pack.overrides.common),
+        ("client-overrides", &pack.overrides.client),
+        ("server-overrides", &pack.overrides.server),
+    ]
+    .into_iter()
+    .flat_map(|(folder, files)| {
+        files
+            .iter()
+            .filter(|(path, _)| path.ends_with(".jar"))
+            .map(move |(path, data)| (format!("{}/{}", folder, path), data.as_slice()))
+    })
+    .collect();
+    let data: Vec<&[u8]> = jars.iter().map(|(_, d)| *d).collect();
+    match curse_files_by_fingerprint(client, &data).await {
+        Ok(found) => jars
+            .into_iter()
+            .zip(found)
+            .filter_map(|((label, _), cref)| cref.map(|c| (label, c)))
+            .collect(),
+        Err(e) => {
+            event!(Level::WARN, "Could not identify override jars: {:#}", e);
+            BTreeMap::new()
+        }
+    }
+}
+
+/**
+ * Finds mods present more than once, by hash, project ids and jar mod ids, and drops all but one copy according to
+ * `policy`
+ *
+ * Only copies that can end up in the same install count, see [ModCopy::shares_side]. Entries should be resolved first
+ * so they carry their hashes, jars are only inspected when their contents are known and `known` (see
+ * [identify_overrides]) gives override jars their curseforge file
+ */
+pub fn dedupe(
+    pack: &mut Pack,
+    policy: DuplicatePolicy,
+    known: &BTreeMap<String, CurseRef>,
+) -> Result<DedupeReport> {
+    let copies = copies(pack, known);
+    let mut groups: Vec<(Vec<&ModCopy>, Vec<String>)> = Vec::new();
+    for copy in &copies {
+        let found = groups.iter_mut().find_map(|(members, reasons)| {
+            let matched: Vec<String> = members
+                .iter()
+                .filter(|m| m.shares_side(copy))
+                .flat_map(|m| m.matches(copy))
+                .collect();
+            (!matched.is_empty()).then_some((members, reasons, matched))
+        });
+        match found {
+            Some((members, reasons, matched)) => {
+                members.push(copy);
+                for reason in matched {
+                    if !reasons.contains(&reason) {
+                        reasons.push(reason);
+                    }
+                }
+            }
+            None => groups.push((vec![copy], Vec::new())),
+        }
+    }
+
+    let mut report = DedupeReport {
+        policy,
+        groups: Vec::new(),
+    };
+    let mut dropped_entries = Vec::new();
+    let mut dropped_overrides = Vec::new();
+    for (members, reasons) in groups.into_iter().filter(|(m, _)| m.len() > 1) {
+        let kept = keep(&members, policy);
+        let mut group = DuplicateGroup {
+            kept: members[kept].label.clone(),
+            dropped: Vec::new(),
+            matched_by: reasons,
+        };
+        for (index, copy) in members.iter().enumerate() {
+            // a copy for the other side only stays, the kept one never reaches its installs
+            if index == kept || !copy.shares_side(members[kept]) {
+                continue;
+            }
+            group.dropped.push(copy.label.clone());
+            match &copy.location {
+                Location::Entry(i) => dropped_entries.push(*i),
+                Location::Override(folder, path) => dropped_overrides.push((*folder, path.clone())),
+            }
+        }
+        if policy != DuplicatePolicy::Error {
+            event!(
+                Level::WARN,
+                "Duplicate mod: kept {}, dropped {} (matched by {})",
+                group.kept,
+                group.dropped.join(", "),
+                group.matched_by.join(", ")
+            );
+        }
+        report.groups.push(group);
+    }
+
+    if policy == DuplicatePolicy::Error && !report.groups.is_empty() {
+        return Err(eyre!("Duplicate mods in pack:\n{}", report));
+    }
+    let mut index = 0;
+    pack.entries.retain(|_| {
+        index += 1;
+        !dropped_entries.contains(&(index - 1))
+    });
+    for (folder, path) in dropped_overrides {
+        let overrides: &mut BTreeMap<String, Vec<u8>> = match folder {
+            "client-overrides" => &mut pack.overrides.client,
+            "server-overrides" => &mut pack.overrides.server,
+            _ => &mut pack.overrides.common,
+        };
+        overrides.remove(&path);
+    }
+    Ok(report)
+}
+
+impl Display for DedupeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for group in &self.groups {
+            writeln!(
+                f,
+                "{} duplicated by {} (matched by {})",
+                group.kept,
+                group.dropped.join(", "),
+                group.matched_by.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt::Display;
+
+use color_eyre::{Result, eyre::Context};
+use serde::Serialize;
+use tracing::{Level, event};
+
+use crate::{
+    diff::{EntrySummary, same_mod},
+    jar,
+    packs::{
+        Dependency, DependencyKind, Pack, PackEntry, ProjectId, curse_dependencies,
+        modrinth_dependencies,
+    },
+    platforms::{
+        ApiClient,
+        curse::{self, APIFile, CurseDependency},
+        mod_data::ModInfo,
+        mr,
+    },
+};
+
+/**
+ * Dependency graph of a pack and every problem found in it
+ */
+#[derive(Serialize, Debug, Default)]
+pub struct DependencyReport {
+    pub graph: Vec<GraphNode>,
+    pub missing: Vec<MissingDependency>,
+    pub incompatible: Vec<Conflict>,
+    pub duplicates: Vec<Conflict>,
+    /// Entries whose dependencies could not be looked up
+    pub unknown: Vec<EntrySummary>,
+}
+/**
+ * An entry and the projects it relates to
+ */
+#[derive(Serialize, Debug)]
+pub struct GraphNode {
+    pub entry: EntrySummary,
+    pub dependencies: Vec<Dependency>,
+}
+/**
+ * Required project no entry of the pack provides
+ */
+#[derive(Serialize, Debug)]
+pub struct MissingDependency {
+    pub entry: EntrySummary,
+    pub project: ProjectId,
+}
+/**
+ * Two entries that should not be in the pack together
+ */
+#[derive(Serialize, Debug)]
+pub struct Conflict {
+    pub entry: EntrySummary,
+    pub other: EntrySummary,
+}
+
+impl DependencyReport {
+    /**
+     * Whether nothing is missing, incompatible or duplicated
+     */
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.incompatible.is_empty() && self.duplicates.is_empty()
+    }
+    pub fn problem_count(&self) -> usize {
+        self.missing.len() + self.incompatible.len() + self.duplicates.len()
+    }
+}
+
+/**
+ * Looks up the dependencies of every entry that does not carry them yet, modrinth is asked first when the entry is
+ * known there
+ *
+ * Entries without a curseforge file are downloaded and looked up by fingerprint first, so a curseforge dependency can be
+ * provided by a copy from another platform
+ */
+pub async fn collect(pack: &mut Pack, client: &ApiClient) -> Result<()> {
+    pack.fetch_contents(client, |e| e.curse.is_none() && !e.downloads.is_empty())
+        .await?;
+    pack.resolve_curse_fingerprints(client).await?;
+    for entry in pack.entries.iter_mut() {
+        if entry.dependencies.is_some() {
+            continue;
+        }
+        if let Some(mref) = &entry.modrinth {
+            let version = mr::lookup_version(client, &mref.version_id).await?;
+            entry.dependencies = Some(modrinth_dependencies(client, &version).await?);
+        } else if let Some(cref) = &entry.curse {
+            let mut mod_info: ModInfo<curse::PackModDescription, APIFile, CurseDependency> =
+                ModInfo::from(curse::PackModDescription {
+                    project_id: cref.project_id,
+                    file_id: cref.file_id,
+                    required: entry.required,
+                })
+                .with_shared_client(client.clone());
+            mod_info.resolve_remotes().await.wrap_err(format!(
+                "Err while resolving curseforge file {}/{}",
+                cref.project_id, cref.file_id
+            ))?;
+            entry.dependencies = mod_info.deps.as_deref().map(curse_dependencies);
+        } else {
+            event!(
+                Level::DEBUG,
+                "{} has no project id, dependencies unknown",
+                entry.label()
+            );
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Whether `entry` is the project `id`
+ */
+fn provides(entry: &PackEntry, id: &ProjectId) -> bool {
+    match id {
+        ProjectId::Curse(project_id) => entry
+            .curse
+            .as_ref()
+            .is_some_and(|c| c.project_id == *project_id),
+        ProjectId::Modrinth(project_id) => entry
+            .modrinth
+            .as_ref()
+            .is_some_and(|m| m.project_id == *project_id),
+    }
+}
+
+/**
+ * Mod ids declared by the jar of `entry`, empty when its contents are unknown or not a mod jar
+ */
+fn jar_mod_ids(entry: &PackEntry) -> Vec<String> {
+    entry
+        .contents
+        .as_deref()
+        .and_then(|c| jar::inspect(c).ok())
+        .map(|mods| mods.into_iter().map(|m| m.mod_id).collect())
+        .unwrap_or_default()
+}
+
+/**
+ * Builds the dependency graph of `pack` and checks it, the pack should be resolved on both platforms and [collect]ed
+ * first so entries carry every project id they have
+ */
+pub fn check(pack: &Pack) -> DependencyReport {
+    let mut report = DependencyReport::default();
+    for (index, entry) in pack.entries.iter().enumerate() {
+        let Some(dependencies) = &entry.dependencies else {
+            report.unknown.push(entry.into());
+            continue;
+        };
+        for dep in dependencies {
+            let mut present = pack
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(other, e)| *other != index && provides(e, &dep.project));
+            match dep.kind {
+                DependencyKind::Required if present.next().is_none() => {
+                    report.missing.push(MissingDependency {
+                        entry: entry.into(),
+                        project: dep.project.clone(),
+                    })
+                }
+                DependencyKind::Incompatible => {
+                    for (_, other) in present {
+                        report.incompatible.push(Conflict {
+                            entry: entry.into(),
+                            other: other.into(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        report.graph.push(GraphNode {
+            entry: entry.into(),
+            dependencies: dependencies.clone(),
+        });
+    }
+    let mod_ids: Vec<Vec<String>> = pack.entries.iter().map(jar_mod_ids).collect();
+    for (index, entry) in pack.entries.iter().enumerate() {
+        for (offset, other) in pack.entries.iter().enumerate().skip(index + 1) {
+            let same_jar_id = mod_ids[index].iter().any(|id| mod_ids[offset].contains(id));
+            if same_mod(entry, other) || same_jar_id {
+                report.duplicates.push(Conflict {
+                    entry: entry.into(),
+                    other: other.into(),
+                });
+            }
+        }
+    }
+    report
+}
+
+impl Display for ProjectId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectId::Curse(id) => write!(f, "curseforge project {}", id),
+            ProjectId::Modrinth(id) => write!(f, "modrinth project {}", id),
+        }
+    }
+}
+impl Display for DependencyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for missing in &self.missing {
+            writeln!(
+                f,
+                "! {} requires missing {}",
+                missing.entry, missing.project
+            )?;
+        }
+        for conflict in &self.incompatible {
+            writeln!(
+                f,
+                "! {} is incompatible with {}",
+                conflict.entry, conflict.other
+            )?;
+        }
+        for conflict in &self.duplicates {
+            writeln!(
+                f,
+                "! {} duplicates {} ({})",
+                conflict.other, conflict.entry, conflict.other.path
+            )?;
+        }
+        for entry in &self.unknown {
+            writeln!(f, "? dependencies of {} are unknown", entry)?;
+        }
+        if self.is_ok() {
+            writeln!(
+                f,
+                "No dependency problems in {} entries",
+                self.graph.len() + self.unknown.len()
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde::Serialize;
+
+use crate::packs::{Pack, PackEntry};
+
+/**
+ * Everything that changed between two packs
+ */
+#[derive(Serialize, Debug, Default)]
+pub struct PackDiff {
+    pub minecraft: Option<Change>,
+    pub loaders: BTreeMap<String, Change>,
+    pub added: Vec<EntrySummary>,
+    pub removed: Vec<EntrySummary>,
+    pub upgraded: Vec<Upgrade>,
+    pub overrides: OverrideDiff,
+}
+/**
+ * A value before and after, [None] when it did not exist on that side
+ */
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Change {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+/**
+ * The parts of a [PackEntry] worth showing in a diff
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct EntrySummary {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: String,
+    pub sha1: Option<String>,
+}
+/**
+ * The same mod present in both packs with a different file
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct Upgrade {
+    pub from: EntrySummary,
+    pub to: EntrySummary,
+}
+/**
+ * Override paths (prefixed by the mrpack folder they belong in) that changed
+ */
+#[derive(Serialize, Debug, Default)]
+pub struct OverrideDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl From<&PackEntry> for EntrySummary {
+    fn from(value: &PackEntry) -> Self {
+        Self {
+            name: value.label(),
+            version: value.version_number.clone(),
+            path: value.path.clone(),
+            sha1: value.sha1.clone(),
+        }
+    }
+}
+impl PackDiff {
+    pub fn is_empty(&self) -> bool {
+        self.minecraft.is_none()
+            && self.loaders.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.upgraded.is_empty()
+            && self.overrides.added.is_empty()
+            && self.overrides.removed.is_empty()
+            && self.overrides.changed.is_empty()
+    }
+}
+
+/**
+ * Whether two entries are the same mod, by hash, curseforge project or modrinth project
+ */
+pub fn same_mod(a: &PackEntry, b: &PackEntry) -> bool {
+    if a.sha1.is_some() && a.sha1 == b.sha1 {
+        return true;
+    }
+    if let (Some(ca), Some(cb)) = (&a.curse, &b.curse)
+        && ca.project_id == cb.project_id
+    {
+        return true;
+    }
+    if let (Some(ma), Some(mb)) = (&a.modrinth, &b.modrinth)
+        && ma.project_id == mb.project_id
+    {
+        return true;
+    }
+    false
+}
+
+/**
+ * Compares two packs, both should be resolved so entries carry hashes and project ids
+ */
+pub fn diff(old: &Pack, new: &Pack) -> PackDiff {
+    let mut out = PackDiff::default();
+    if old.minecraft != new.minecraft {
+        out.minecraft = Some(Change {
+            from: Some(old.minecraft.clone()),
+            to: Some(new.minecraft.clone()),
+        });
+    }
+    for key in old.loaders.keys().chain(new.loaders.keys()) {
+        let (from, to) = (old.loaders.get(key), new.loaders.get(key));
+        if from != to {
+            out.loaders.insert(
+                key.clone(),
+                Change {
+                    from: from.cloned(),
+                    to: to.cloned(),
+                },
+            );
+        }
+    }
+
+    let mut unmatched: Vec<&PackEntry> = new.entries.iter().collect();
+    for old_entry in &old.entries {
+        let found = unmatched.iter().position(|e| same_mod(old_entry, e));
+        match found {
+            Some(index) => {
+                let new_entry = unmatched.remove(index);
+                if old_entry.sha1 != new_entry.sha1 {
+                    out.upgraded.push(Upgrade {
+                        from: old_entry.into(),
+                        to: new_entry.into(),
+                    });
+                }
+            }
+            None => out.removed.push(old_entry.into()),
+        }
+    }
+    out.added = unmatched.into_iter().map(EntrySummary::from).collect();
+
+    for (prefix, from, to) in [
+        ("overrides", &old.overrides.common, &new.overrides.common),
+        (
+            "client-overrides",
+            &old.overrides.client,
+            &new.overrides.client,
+        ),
+        (
+            "server-overrides",
+            &old.overrides.server,
+            &new.overrides.server,
+        ),
+    ] {
+        for (path, data) in from {
+            match to.get(path) {
+                None => out.overrides.removed.push(format!("{}/{}", prefix, path)),
+                Some(other) if other != data => {
+                    out.overrides.changed.push(format!("{}/{}", prefix, path))
+                }
+                Some(_) => {}
+            }
+        }
+        for path in to.keys().filter(|p| !from.contains_key(*p)) {
+            out.overrides.added.push(format!("{}/{}", prefix, path));
+        }
+    }
+    out
+}
+
+impl Display for EntrySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} ({})", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} -> {}",
+            self.from.as_deref().unwrap_or("none"),
+            self.to.as_deref().unwrap_or("none")
+        )
+    }
+}
+impl Display for PackDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        if let Some(change) = &self.minecraft {
+            writeln!(f, "Minecraft: {}", change)?;
+        }
+        for (loader, change) in &self.loaders {
+            writeln!(f, "{}: {}", loader, change)?;
+        }
+        for entry in &self.added {
+            writeln!(f, "+ {}", entry)?;
+        }
+        for entry in &self.removed {
+            writeln!(f, "- {}", entry)?;
+        }
+        for upgrade in &self.upgraded {
+            writeln!(f, "~ {} -> {}", upgrade.from, upgrade.to)?;
+        }
+        for path in &self.overrides.added {
+            writeln!(f, "+ {}", path)?;
+        }
+        for path in &self.overrides.removed {
+            writeln!(f, "- {}", path)?;
+        }
+        for path in &self.overrides.changed {
+            writeln!(f, "~ {}", path)?;
+        }
+        Ok(())
+    }
+}
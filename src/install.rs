@@ -0,0 +1,403 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{Level, event};
+
+use crate::{
+    packs::{CurseRef, ModrinthRef, Pack, PackEntry, is_safe_path, sha1_hex},
+    platforms::ApiClient,
+    progress::{self, Progress},
+};
+
+/**
+ * Name of the lockfile written into the root of an installed pack
+ */
+pub const LOCKFILE_NAME: &str = "multipack.lock.json";
+
+/**
+ * Which side of the game a pack is installed for
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    #[default]
+    Client,
+    Server,
+}
+impl Side {
+    /**
+     * Reads the side from `INSTALL_SIDE`, defaulting to [Side::Client]
+     */
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("INSTALL_SIDE")
+            .unwrap_or("CLIENT".to_string())
+            .to_uppercase()
+            .as_str()
+        {
+            "CLIENT" => Ok(Side::Client),
+            "SERVER" => Ok(Side::Server),
+            other => Err(eyre!("Unknown install side {}", other)),
+        }
+    }
+    /**
+     * Whether `entry` should be installed on this side, only modrinth `unsupported` excludes an entry
+     */
+    pub fn wants(&self, entry: &PackEntry) -> bool {
+        match (&entry.env, self) {
+            (None, _) => true,
+            (Some(env), Side::Client) => env.client != "unsupported",
+            (Some(env), Side::Server) => env.server != "unsupported",
+        }
+    }
+    /**
+     * Whether `entry` is optional on this side, because it is not required or its modrinth env says so for this side
+     */
+    pub fn optional(&self, entry: &PackEntry) -> bool {
+        !entry.required
+            || entry.env.as_ref().is_some_and(|env| match self {
+                Side::Client => env.client == "optional",
+                Side::Server => env.server == "optional",
+            })
+    }
+}
+
+/**
+ * Optional entries (curseforge `required: false`, modrinth `optional` env) an install opts into, required entries are
+ * always installed
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionalMods {
+    #[default]
+    None,
+    All,
+    /// Entries whose name or file name is listed, case insensitive
+    Only(Vec<String>),
+}
+impl OptionalMods {
+    /**
+     * Reads `all`, `none` or a comma separated list of mod or file names
+     */
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "all" => OptionalMods::All,
+            "none" | "" => OptionalMods::None,
+            _ => OptionalMods::Only(
+                value
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect(),
+            ),
+        }
+    }
+    /**
+     * Reads the selection from `INSTALL_OPTIONAL`, defaulting to [OptionalMods::None]
+     */
+    pub fn from_env() -> Self {
+        std::env::var("INSTALL_OPTIONAL")
+            .map(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+    /**
+     * Whether `entry` is installed on `side`, entries not optional there always are
+     */
+    pub fn wants(&self, entry: &PackEntry, side: Side) -> bool {
+        match self {
+            _ if !side.optional(entry) => true,
+            OptionalMods::None => false,
+            OptionalMods::All => true,
+            OptionalMods::Only(names) => names.iter().any(|name| {
+                name.eq_ignore_ascii_case(&entry.label())
+                    || name.eq_ignore_ascii_case(entry.file_name())
+            }),
+        }
+    }
+}
+
+/**
+ * Whether `entry` goes into an install for `side` with `optional`, logging optional entries left out
+ */
+pub fn selected(entry: &PackEntry, side: Side, optional: &OptionalMods) -> bool {
+    if !side.wants(entry) {
+        return false;
+    }
+    let wanted = optional.wants(entry, side);
+    if !wanted {
+        event!(
+            Level::INFO,
+            "Skipping optional {}, opt in with --optional \"{}\"",
+            entry.label(),
+            entry.label()
+        );
+    }
+    wanted
+}
+
+/**
+ * Record of everything an install wrote, used to update the install later
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Lockfile {
+    pub name: String,
+    pub version: String,
+    pub minecraft: String,
+    pub loaders: BTreeMap<String, String>,
+    pub side: Side,
+    #[serde(default)]
+    pub optional: OptionalMods,
+    pub entries: Vec<LockedEntry>,
+    /// sha1 of every override file as written, keyed by path
+    pub overrides: BTreeMap<String, String>,
+}
+/**
+ * An installed [PackEntry]
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockedEntry {
+    pub path: String,
+    pub display_name: Option<String>,
+    pub sha1: String,
+    pub sha512: Option<String>,
+    pub file_size: u64,
+    pub curse: Option<CurseRef>,
+    pub modrinth: Option<ModrinthRef>,
+}
+impl Lockfile {
+    /**
+     * Loads the lockfile of an installed pack, rejecting it if any path escapes `dir`
+     */
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(LOCKFILE_NAME);
+        let lock: Self = serde_json::from_slice(
+            &fs::read(&path).wrap_err(format!("Err while reading {}", path.display()))?,
+        )
+        .wrap_err("Err while parsing lockfile")?;
+        let paths = lock.entries.iter().map(|e| &e.path);
+        match paths
+            .chain(lock.overrides.keys())
+            .find(|p| !is_safe_path(p))
+        {
+            Some(unsafe_path) => Err(eyre!(
+                "Err while loading lockfile: {} escapes the install folder",
+                unsafe_path
+            )),
+            None => Ok(lock),
+        }
+    }
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::write(dir.join(LOCKFILE_NAME), serde_json::to_vec_pretty(self)?)
+            .wrap_err("Err while writing lockfile")
+    }
+}
+
+/**
+ * Overrides for `side` in the order they are applied, later maps win
+ */
+pub fn merged_overrides(pack: &Pack, side: Side) -> BTreeMap<&String, &Vec<u8>> {
+    let mut merged: BTreeMap<&String, &Vec<u8>> = pack.overrides.common.iter().collect();
+    merged.extend(match side {
+        Side::Client => pack.overrides.client.iter(),
+        Side::Server => pack.overrides.server.iter(),
+    });
+    merged
+}
+
+/**
+ * Writes every entry wanted on `side` and selected by `optional` and the overrides into `dir`, entries must already
+ * carry their contents
+ */
+pub fn install(pack: &Pack, dir: &Path, side: Side, optional: &OptionalMods) -> Result<Lockfile> {
+    fs::create_dir_all(dir).wrap_err(format!("Err while creating {}", dir.display()))?;
+    let mut lock = Lockfile {
+        name: pack.name.clone(),
+        version: pack.version.clone(),
+        minecraft: pack.minecraft.clone(),
+        loaders: pack.loaders.clone(),
+        side,
+        optional: optional.clone(),
+        ..Default::default()
+    };
+    progress::report(&pack.progress, Progress::Writing);
+    for entry in pack.entries.iter().filter(|e| selected(e, side, optional)) {
+        let contents = entry
+            .contents
+            .as_ref()
+            .ok_or(eyre!("{} was not downloaded", entry.label()))?;
+        event!(Level::DEBUG, "Installing {}", entry.path);
+        write_file(dir, &entry.path, contents)?;
+        lock.entries.push(LockedEntry {
+            path: entry.path.clone(),
+            display_name: entry.display_name.clone(),
+            sha1: entry.sha1.clone().unwrap_or(sha1_hex(contents)),
+            sha512: entry.sha512.clone(),
+            file_size: contents.len() as u64,
+            curse: entry.curse.clone(),
+            modrinth: entry.modrinth.clone(),
+        });
+    }
+    for (path, data) in merged_overrides(pack, side) {
+        event!(Level::DEBUG, "Applying override {}", path);
+        write_file(dir, path, data)?;
+        lock.overrides.insert(path.clone(), sha1_hex(data));
+    }
+    lock.save(dir)?;
+    progress::report(&pack.progress, Progress::Done);
+    event!(
+        Level::INFO,
+        "Installed {} entries and {} overrides into {}",
+        lock.entries.len(),
+        lock.overrides.len(),
+        dir.display()
+    );
+    Ok(lock)
+}
+
+/**
+ * Brings an install made by [install] up to date with `pack`
+ *
+ * Only entries whose file on disk does not match are downloaded, entries dropped from the pack are removed, and
+ * overrides the user edited since the last install (their hash no longer matches the lockfile) are left alone
+ */
+pub async fn update(pack: &mut Pack, dir: &Path, client: &ApiClient) -> Result<Lockfile> {
+    let old = Lockfile::load(dir)?;
+    let (side, optional) = (old.side, old.optional.clone());
+    event!(
+        Level::INFO,
+        "Updating {} {} -> {}",
+        old.name,
+        old.version,
+        pack.version
+    );
+    pack.resolve_curse(client).await?;
+    let up_to_date = |entry: &PackEntry| match &entry.sha1 {
+        Some(sha1) => hash_file(&dir.join(&entry.path)).as_ref() == Some(sha1),
+        None => false,
+    };
+    pack.fetch_contents(client, |entry| {
+        side.wants(entry) && optional.wants(entry, side) && !up_to_date(entry)
+    })
+    .await?;
+
+    let mut lock = Lockfile {
+        name: pack.name.clone(),
+        version: pack.version.clone(),
+        minecraft: pack.minecraft.clone(),
+        loaders: pack.loaders.clone(),
+        side,
+        optional: optional.clone(),
+        ..Default::default()
+    };
+    progress::report(&pack.progress, Progress::Writing);
+    let (mut written, mut kept, mut removed) = (0, 0, 0);
+    for entry in pack.entries.iter().filter(|e| selected(e, side, &optional)) {
+        let sha1 = match &entry.contents {
+            Some(contents) => {
+                event!(Level::DEBUG, "Installing {}", entry.path);
+                write_file(dir, &entry.path, contents)?;
+                written += 1;
+                sha1_hex(contents)
+            }
+            None => {
+                kept += 1;
+                entry.sha1.clone().unwrap_or_default()
+            }
+        };
+        lock.entries.push(LockedEntry {
+            path: entry.path.clone(),
+            display_name: entry.display_name.clone(),
+            sha1,
+            sha512: entry.sha512.clone(),
+            file_size: entry.file_size.unwrap_or_default(),
+            curse: entry.curse.clone(),
+            modrinth: entry.modrinth.clone(),
+        });
+    }
+    for old_entry in &old.entries {
+        if lock.entries.iter().any(|e| e.path == old_entry.path) {
+            continue;
+        }
+        let target = dir.join(&old_entry.path);
+        if target.exists() {
+            event!(Level::INFO, "Removing {}", old_entry.path);
+            fs::remove_file(&target)
+                .wrap_err(format!("Err while removing {}", target.display()))?;
+            removed += 1;
+        }
+    }
+
+    let new_overrides = merged_overrides(pack, side);
+    for (path, data) in &new_overrides {
+        let new_hash = sha1_hex(data);
+        let on_disk = hash_file(&dir.join(path));
+        let edited = match (&on_disk, old.overrides.get(*path)) {
+            (None, _) => false,
+            (Some(disk), _) if *disk == new_hash => false,
+            (Some(disk), Some(locked)) => disk != locked,
+            (Some(_), None) => true,
+        };
+        if edited {
+            event!(Level::WARN, "Keeping user edited {}", path);
+        } else if on_disk.as_ref() != Some(&new_hash) {
+            event!(Level::DEBUG, "Applying override {}", path);
+            write_file(dir, path, data)?;
+        }
+        lock.overrides.insert((*path).clone(), new_hash);
+    }
+    for (path, locked) in &old.overrides {
+        if new_overrides.contains_key(path) {
+            continue;
+        }
+        match hash_file(&dir.join(path)) {
+            Some(disk) if disk == *locked => {
+                event!(Level::INFO, "Removing override {}", path);
+                fs::remove_file(dir.join(path))?;
+            }
+            Some(_) => event!(Level::WARN, "Keeping user edited {}", path),
+            None => {}
+        }
+    }
+    lock.save(dir)?;
+    progress::report(&pack.progress, Progress::Done);
+    event!(
+        Level::INFO,
+        "{} entries written, {} unchanged, {} removed",
+        written,
+        kept,
+        removed
+    );
+    Ok(lock)
+}
+
+/**
+ * sha1 of the file at `path`, [None] if it cannot be read
+ */
+pub(crate) fn hash_file(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|data| sha1_hex(&data))
+}
+
+/**
+ * Writes `data` to `path` below `dir`, creating parent folders
+ */
+pub(crate) fn write_file(dir: &Path, path: &str, data: &[u8]) -> Result<()> {
+    if !is_safe_path(path) {
+        return Err(eyre!(
+            "Refusing to write {} outside of {}",
+            path,
+            dir.display()
+        ));
+    }
+    let target: PathBuf = dir.join(path);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).wrap_err(format!("Err while creating {}", parent.display()))?;
+    }
+    fs::write(&target, data).wrap_err(format!("Err while writing {}", target.display()))
+}
@@ -0,0 +1,401 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    io::{Cursor, Read, Seek},
+};
+
+use color_eyre::{Result, eyre::Context};
+use serde::{Deserialize, Serialize};
+use tracing::{Level, event};
+use zip::ZipArchive;
+
+use crate::{
+    packs::{ArchiveLimits, DependencyKind, Pack, check_archive, read_entry},
+    platforms::mr::ModrinthEnvironmentRequirement,
+};
+
+/**
+ * A mod declared by the metadata file of a jar
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct JarMod {
+    pub mod_id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// Modrinth dependency key of the loader the metadata file belongs to
+    pub loader: String,
+    /// Sides the mod runs on, only fabric and quilt declare this
+    pub env: Option<ModrinthEnvironmentRequirement>,
+    pub dependencies: Vec<JarDependency>,
+}
+/**
+ * Mod id a [JarMod] relates to, including `minecraft`, `java` and the loader itself
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct JarDependency {
+    pub mod_id: String,
+    pub kind: DependencyKind,
+    pub version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FabricModJson {
+    id: String,
+    version: String,
+    name: Option<String>,
+    environment: Option<String>,
+    #[serde(default)]
+    depends: BTreeMap<String, VersionSpec>,
+    #[serde(default)]
+    recommends: BTreeMap<String, VersionSpec>,
+    #[serde(default)]
+    suggests: BTreeMap<String, VersionSpec>,
+    #[serde(default)]
+    breaks: BTreeMap<String, VersionSpec>,
+    #[serde(default)]
+    conflicts: BTreeMap<String, VersionSpec>,
+}
+#[derive(Deserialize)]
+#[serde(untagged)]
+#[allow(unused)]
+enum VersionSpec {
+    One(String),
+    Many(Vec<String>),
+    Other(serde_json::Value),
+}
+
+#[derive(Deserialize)]
+struct QuiltModJson {
+    quilt_loader: QuiltLoader,
+    minecraft: Option<QuiltMinecraft>,
+}
+#[derive(Deserialize)]
+struct QuiltLoader {
+    id: String,
+    version: String,
+    metadata: Option<QuiltMetadata>,
+    #[serde(default)]
+    depends: Vec<QuiltDependency>,
+    #[serde(default)]
+    breaks: Vec<QuiltDependency>,
+}
+#[derive(Deserialize)]
+struct QuiltMetadata {
+    name: Option<String>,
+}
+#[derive(Deserialize)]
+struct QuiltMinecraft {
+    environment: Option<String>,
+}
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QuiltDependency {
+    Id(String),
+    Full {
+        id: String,
+        versions: Option<VersionSpec>,
+        #[serde(default)]
+        optional: bool,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModsToml {
+    #[serde(default)]
+    mods: Vec<TomlMod>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Vec<TomlDependency>>,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TomlMod {
+    mod_id: String,
+    version: Option<String>,
+    display_name: Option<String>,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TomlDependency {
+    mod_id: String,
+    /// Forge and older neoforge
+    mandatory: Option<bool>,
+    /// Neoforge, `required`, `optional`, `incompatible` or `discouraged`
+    #[serde(rename = "type")]
+    dependency_type: Option<String>,
+    version_range: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum McModInfo {
+    List(Vec<McMod>),
+    Wrapped {
+        #[serde(rename = "modList")]
+        mod_list: Vec<McMod>,
+    },
+}
+#[derive(Deserialize)]
+struct McMod {
+    modid: String,
+    name: Option<String>,
+    version: Option<String>,
+    /// `modid@versionrange`
+    #[serde(default, rename = "requiredMods")]
+    required_mods: Vec<String>,
+}
+
+impl VersionSpec {
+    fn into_string(self) -> Option<String> {
+        match self {
+            VersionSpec::One(v) => Some(v),
+            VersionSpec::Many(v) => Some(v.join(" || ")),
+            VersionSpec::Other(_) => None,
+        }
+    }
+}
+
+/**
+ * Maps a fabric `environment` or quilt `minecraft.environment` onto modrinth's client/server requirement
+ */
+fn environment(value: &str) -> Option<ModrinthEnvironmentRequirement> {
+    let (client, server) = match value {
+        "*" => ("required", "required"),
+        "client" => ("required", "unsupported"),
+        "server" | "dedicated_server" => ("unsupported", "required"),
+        _ => return None,
+    };
+    Some(ModrinthEnvironmentRequirement {
+        client: client.to_string(),
+        server: server.to_string(),
+    })
+}
+
+fn parse_fabric(data: &[u8]) -> Result<JarMod> {
+    let meta: FabricModJson =
+        serde_json::from_slice(data).wrap_err("Err while parsing fabric.mod.json")?;
+    let mut dependencies = Vec::new();
+    for (map, kind) in [
+        (meta.depends, DependencyKind::Required),
+        (meta.recommends, DependencyKind::Optional),
+        (meta.suggests, DependencyKind::Optional),
+        (meta.breaks, DependencyKind::Incompatible),
+        (meta.conflicts, DependencyKind::Incompatible),
+    ] {
+        dependencies.extend(map.into_iter().map(|(mod_id, spec)| JarDependency {
+            mod_id,
+            kind,
+            version: spec.into_string(),
+        }));
+    }
+    Ok(JarMod {
+        mod_id: meta.id,
+        name: meta.name,
+        version: Some(meta.version),
+        loader: "fabric-loader".to_string(),
+        env: meta.environment.as_deref().and_then(environment),
+        dependencies,
+    })
+}
+
+fn parse_quilt(data: &[u8]) -> Result<JarMod> {
+    let meta: QuiltModJson =
+        serde_json::from_slice(data).wrap_err("Err while parsing quilt.mod.json")?;
+    let loader = meta.quilt_loader;
+    let mut dependencies = Vec::new();
+    for (deps, incompatible) in [(loader.depends, false), (loader.breaks, true)] {
+        dependencies.extend(deps.into_iter().map(|dep| {
+            let (mod_id, version, optional) = match dep {
+                QuiltDependency::Id(id) => (id, None, false),
+                QuiltDependency::Full {
+                    id,
+                    versions,
+                    optional,
+                } => (id, versions.and_then(VersionSpec::into_string), optional),
+            };
+            JarDependency {
+                mod_id,
+                kind: match (incompatible, optional) {
+                    (true, _) => DependencyKind::Incompatible,
+                    (false, true) => DependencyKind::Optional,
+                    (false, false) => DependencyKind::Required,
+                },
+                version,
+            }
+        }));
+    }
+    Ok(JarMod {
+        mod_id: loader.id,
+        name: loader.metadata.and_then(|m| m.name),
+        version: Some(loader.version),
+        loader: "quilt-loader".to_string(),
+        env: meta
+            .minecraft
+            .and_then(|m| m.environment)
+            .as_deref()
+            .and_then(environment),
+        dependencies,
+    })
+}
+
+/**
+ * Parses a forge or neoforge mods.toml, `${file.jarVersion}` is replaced with `jar_version`
+ */
+fn parse_mods_toml(data: &[u8], loader: &str, jar_version: Option<&str>) -> Result<Vec<JarMod>> {
+    let meta: ModsToml =
+        toml::from_str(std::str::from_utf8(data).wrap_err("mods.toml is not valid utf-8")?)
+            .wrap_err("Err while parsing mods.toml")?;
+    let mut dependencies = meta.dependencies;
+    Ok(meta
+        .mods
+        .into_iter()
+        .map(|m| JarMod {
+            dependencies: dependencies
+                .remove(&m.mod_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|dep| JarDependency {
+                    mod_id: dep.mod_id,
+                    kind: match (dep.dependency_type.as_deref(), dep.mandatory) {
+                        (Some(t), _) => match t.to_lowercase().as_str() {
+                            "required" => DependencyKind::Required,
+                            "incompatible" => DependencyKind::Incompatible,
+                            _ => DependencyKind::Optional,
+                        },
+                        (None, Some(false)) => DependencyKind::Optional,
+                        (None, _) => DependencyKind::Required,
+                    },
+                    version: dep.version_range,
+                })
+                .collect(),
+            mod_id: m.mod_id,
+            name: m.display_name,
+            version: match (m.version, jar_version) {
+                (Some(v), Some(jar)) if v == "${file.jarVersion}" => Some(jar.to_string()),
+                (v, _) => v,
+            },
+            loader: loader.to_string(),
+            env: None,
+        })
+        .collect())
+}
+
+fn parse_mcmod_info(data: &[u8]) -> Result<Vec<JarMod>> {
+    let info: McModInfo = serde_json::from_slice(data).wrap_err("Err while parsing mcmod.info")?;
+    let mods = match info {
+        McModInfo::List(mods) => mods,
+        McModInfo::Wrapped { mod_list } => mod_list,
+    };
+    Ok(mods
+        .into_iter()
+        .map(|m| JarMod {
+            mod_id: m.modid,
+            name: m.name,
+            version: m.version,
+            loader: "forge".to_string(),
+            env: None,
+            dependencies: m
+                .required_mods
+                .into_iter()
+                .map(|req| {
+                    let (mod_id, version) = match req.split_once('@') {
+                        Some((id, range)) => (id.to_string(), Some(range.to_string())),
+                        None => (req, None),
+                    };
+                    JarDependency {
+                        mod_id,
+                        kind: DependencyKind::Required,
+                        version,
+                    }
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/**
+ * `Implementation-Version` from the jar manifest, what forge substitutes for `${file.jarVersion}`
+ */
+fn manifest_version<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<String> {
+    let index = archive.index_for_name("META-INF/MANIFEST.MF")?;
+    let data = read_entry(archive, index).ok()?;
+    String::from_utf8_lossy(&data)
+        .lines()
+        .find_map(|l| l.strip_prefix("Implementation-Version:"))
+        .map(|v| v.trim().to_string())
+}
+
+/**
+ * Reads every mod declared by the metadata files of a jar, a jar built for several loaders yields one [JarMod] per
+ * loader and an empty list means the jar has no metadata this knows about
+ */
+pub fn inspect(data: &[u8]) -> Result<Vec<JarMod>> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).wrap_err("Err while opening jar")?;
+    check_archive(&mut archive, &ArchiveLimits::default())?;
+    let mut mods = Vec::new();
+    if let Some(index) = archive.index_for_name("quilt.mod.json") {
+        mods.push(parse_quilt(&read_entry(&mut archive, index)?)?);
+    }
+    if let Some(index) = archive.index_for_name("fabric.mod.json") {
+        mods.push(parse_fabric(&read_entry(&mut archive, index)?)?);
+    }
+    for (name, loader) in [
+        ("META-INF/neoforge.mods.toml", "neoforge"),
+        ("META-INF/mods.toml", "forge"),
+    ] {
+        if let Some(index) = archive.index_for_name(name) {
+            let data = read_entry(&mut archive, index)?;
+            let jar_version = manifest_version(&mut archive);
+            mods.extend(parse_mods_toml(&data, loader, jar_version.as_deref())?);
+        }
+    }
+    if let Some(index) = archive.index_for_name("mcmod.info") {
+        mods.extend(parse_mcmod_info(&read_entry(&mut archive, index)?)?);
+    }
+    Ok(mods)
+}
+
+/**
+ * Inspects every jar the pack carries, entries with contents and jars shipped as overrides, keyed by path
+ *
+ * Jars that fail to parse are logged and skipped
+ */
+pub fn inspect_pack(pack: &Pack) -> BTreeMap<String, Vec<JarMod>> {
+    let carried = pack
+        .entries
+        .iter()
+        .filter_map(|e| e.contents.as_ref().map(|c| (&e.path, c)));
+    let overrides = pack
+        .overrides
+        .common
+        .iter()
+        .chain(pack.overrides.client.iter())
+        .chain(pack.overrides.server.iter());
+    let mut out = BTreeMap::new();
+    for (path, data) in carried.chain(overrides) {
+        if !path.ends_with(".jar") {
+            continue;
+        }
+        match inspect(data) {
+            Ok(mods) => {
+                out.insert(path.clone(), mods);
+            }
+            Err(e) => event!(Level::WARN, "Could not inspect {}: {}", path, e),
+        }
+    }
+    out
+}
+
+impl Display for JarMod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.mod_id)?;
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        write!(f, " ({})", self.loader)?;
+        if let Some(env) = &self.env {
+            write!(f, " client: {}, server: {}", env.client, env.server)?;
+        }
+        Ok(())
+    }
+}
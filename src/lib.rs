@@ -0,0 +1,249 @@
+use std::io::{Read, Seek, Write};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
+};
+use reqwest::header::{HeaderMap, HeaderValue};
+use zip::{ZipArchive, ZipWriter};
+
+pub mod dedupe;
+pub mod deps;
+pub mod diff;
+pub mod install;
+pub mod jar;
+pub mod outdated;
+pub mod packs;
+pub mod platforms;
+pub mod port;
+pub mod progress;
+pub mod report;
+pub mod roundtrip;
+
+use dedupe::{DedupeReport, DuplicatePolicy};
+use packs::{Compression, MetadataOverrides, Pack, PackFormat, modrinth_pack::DownloadPolicy};
+use platforms::{ApiClient, ApiUrls};
+use progress::{Progress, ProgressSender};
+
+/**
+ * Format a pack is written in
+ */
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputFormat {
+    MODRINTH,
+    CURSEFORGE,
+    TECHNICPACK,
+    ATLAUNCHER,
+    GDLAUNCHER,
+    /// Installed into a folder, see [install::install]
+    DIRECTORY,
+    OTHER(String),
+}
+impl From<&str> for OutputFormat {
+    /**
+     * Reads an `OUTPUT_FORMAT` value
+     */
+    fn from(value: &str) -> Self {
+        match value {
+            "MODRINTH" => OutputFormat::MODRINTH,
+            "CURSE" => OutputFormat::CURSEFORGE,
+            "TECHNIC" => OutputFormat::TECHNICPACK,
+            "ATLAUNCHER" => OutputFormat::ATLAUNCHER,
+            "GDLAUNCHER" => OutputFormat::GDLAUNCHER,
+            "DIRECTORY" => OutputFormat::DIRECTORY,
+            other => OutputFormat::OTHER(other.to_string()),
+        }
+    }
+}
+impl OutputFormat {
+    /**
+     * Output format writing a pack back in the format it was read from
+     */
+    pub fn same_as(format: Option<PackFormat>) -> Result<Self> {
+        match format {
+            Some(PackFormat::Modrinth) => Ok(OutputFormat::MODRINTH),
+            Some(PackFormat::ATLauncher) => Ok(OutputFormat::ATLAUNCHER),
+            Some(PackFormat::GDLauncher) => Ok(OutputFormat::GDLAUNCHER),
+            Some(PackFormat::Curse) => Ok(OutputFormat::CURSEFORGE),
+            None => Err(eyre!("Input is not a known modpack format")),
+        }
+    }
+}
+
+/**
+ * Settings for [convert]
+ */
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub format: OutputFormat,
+    pub duplicates: DuplicatePolicy,
+    pub compression: Compression,
+    /// What modrinth output does with files it may not link to
+    pub downloads: DownloadPolicy,
+    pub metadata: MetadataOverrides,
+    /// Shared client for every api call and download, see [build_client]
+    pub client: ApiClient,
+    pub progress: Option<ProgressSender>,
+}
+impl ConvertOptions {
+    pub fn new(format: OutputFormat, client: ApiClient) -> Self {
+        Self {
+            format,
+            duplicates: DuplicatePolicy::default(),
+            compression: Compression::default(),
+            downloads: DownloadPolicy::default(),
+            metadata: MetadataOverrides::default(),
+            client,
+            progress: None,
+        }
+    }
+}
+
+/**
+ * Builds a client for the curseforge and modrinth apis, curseforge needs `curse_api_key`
+ *
+ * The api base urls come from [ApiUrls::from_env], see [ApiClient::with_urls] to point it elsewhere
+ */
+pub fn build_client(curse_api_key: &str) -> Result<ApiClient> {
+    let http = reqwest::Client::builder()
+        .user_agent("crinfarr/multipak @indev (dev@crinfarr.io)")
+        .default_headers({
+            let mut hm = HeaderMap::new();
+            hm.append(
+                "x-api-key",
+                HeaderValue::from_str(curse_api_key).wrap_err("Invalid curse api key")?,
+            );
+
+            hm
+        })
+        .build()
+        .wrap_err("Err while building network client")?;
+    Ok(ApiClient {
+        http,
+        urls: ApiUrls::from_env(),
+    })
+}
+
+/**
+ * A pack going through [convert], with what [dedupe::dedupe] chose once it ran
+ */
+#[derive(Debug)]
+pub struct Conversion {
+    pub pack: Pack,
+    pub duplicates: Option<DedupeReport>,
+}
+impl Conversion {
+    /**
+     * Reads a pack archive in any supported format from `input` and applies `opts.metadata`
+     */
+    pub fn read(input: impl Read + Seek, opts: &ConvertOptions) -> Result<Self> {
+        progress::report(&opts.progress, Progress::Reading);
+        let mut archive = ZipArchive::new(input).wrap_err("Err while reading input zip")?;
+        let mut pack = packs::read_archive(&mut archive)?;
+        opts.metadata.apply(&mut pack);
+        pack.progress = opts.progress.clone();
+        Ok(Self {
+            pack,
+            duplicates: None,
+        })
+    }
+    /**
+     * Resolves curseforge entries, identifies override jars and removes duplicates as `opts.duplicates` says
+     */
+    pub async fn prepare(&mut self, opts: &ConvertOptions) -> Result<()> {
+        self.pack.resolve_curse(&opts.client).await?;
+        let known = dedupe::identify_overrides(&self.pack, &opts.client).await;
+        self.duplicates = Some(dedupe::dedupe(&mut self.pack, opts.duplicates, &known)?);
+        Ok(())
+    }
+    /**
+     * Writes the prepared pack to `output` in `opts.format`, see [write_pack]
+     */
+    pub async fn write(&mut self, output: impl Write + Seek, opts: &ConvertOptions) -> Result<()> {
+        write_pack(
+            &mut self.pack,
+            &opts.format,
+            output,
+            &opts.compression,
+            opts.downloads,
+            &opts.client,
+        )
+        .await
+    }
+}
+
+/**
+ * Reads a pack archive in any supported format from `input` and writes it to `output` in `opts.format`
+ *
+ * Returns the pack as written with the duplicates that were dropped, [OutputFormat::DIRECTORY] is not a zip and has
+ * to go through [install::install]
+ */
+pub async fn convert(
+    input: impl Read + Seek,
+    output: impl Write + Seek,
+    opts: &ConvertOptions,
+) -> Result<Conversion> {
+    let mut conversion = Conversion::read(input, opts)?;
+    conversion.prepare(opts).await?;
+    conversion.write(output, opts).await?;
+    Ok(conversion)
+}
+
+/**
+ * Resolves and downloads whatever `format` needs and writes the pack archive to `output`, compressing files as
+ * `compression` says and handling files a modrinth pack may not link to as `downloads` says
+ */
+pub async fn write_pack(
+    pack: &mut Pack,
+    format: &OutputFormat,
+    output: impl Write + Seek,
+    compression: &Compression,
+    downloads: DownloadPolicy,
+    client: &ApiClient,
+) -> Result<()> {
+    compression.check()?;
+    let mut out_writer = ZipWriter::new(output);
+    match format {
+        OutputFormat::MODRINTH => {
+            pack.resolve_curse(client).await?;
+            pack.resolve_modrinth(client).await?;
+            pack.fetch_contents(client, |entry| downloads.needs_contents(entry))
+                .await?;
+            progress::report(&pack.progress, Progress::Writing);
+            packs::modrinth_pack::write(pack, &mut out_writer, compression, downloads)?;
+        }
+        OutputFormat::ATLAUNCHER => {
+            pack.resolve_curse(client).await?;
+            pack.fetch_contents(client, |_| true).await?;
+            progress::report(&pack.progress, Progress::Writing);
+            packs::atlauncher_pack::write(pack, &mut out_writer, compression)?;
+        }
+        OutputFormat::GDLAUNCHER => {
+            pack.resolve_curse(client).await?;
+            pack.fetch_contents(client, |_| true).await?;
+            progress::report(&pack.progress, Progress::Writing);
+            packs::gdlauncher_pack::write(pack, &mut out_writer, compression)?;
+        }
+        OutputFormat::CURSEFORGE => {
+            pack.resolve_curse(client).await?;
+            pack.fetch_contents(client, |entry| entry.curse.is_none())
+                .await?;
+            // files curseforge hosts too go into the manifest instead of overrides
+            pack.resolve_curse_fingerprints(client).await?;
+            let projects = packs::curse_pack::lookup_projects(pack, client).await?;
+            progress::report(&pack.progress, Progress::Writing);
+            packs::curse_pack::write(pack, &mut out_writer, compression, &projects)?;
+        }
+        OutputFormat::TECHNICPACK => return Err(eyre!("Technic is not yet supported")),
+        OutputFormat::DIRECTORY => {
+            return Err(eyre!(
+                "Directory output is not an archive, use install::install"
+            ));
+        }
+        OutputFormat::OTHER(s) => return Err(eyre!("Format {} not supported", s)),
+    };
+    out_writer.finish()?;
+    progress::report(&pack.progress, Progress::Done);
+    Ok(())
+}
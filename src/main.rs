@@ -1,29 +1,26 @@
-use color_eyre::{Result, eyre::Context};
-use reqwest::header::{HeaderMap, HeaderValue};
-use std::{
-    fs::File,
-    io::{Read, Write},
-    sync::Arc,
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
 };
-use tokio::{sync::Mutex, task::JoinSet, time::Instant};
-use tracing::{Instrument, Level, event, span};
-use zip::{ZipWriter, write::SimpleFileOptions};
-
-use crate::platforms::mod_data::ModInfo;
-mod platforms;
-
-enum OutputFormat {
-    MODRINTH,
-    CURSEFORGE,
-    TECHNICPACK,
-    OTHER(String),
-}
+use indicatif::{ProgressBar, ProgressStyle};
+use multipack::{
+    Conversion, ConvertOptions, OutputFormat, build_client, dedupe, deps, diff, install, jar,
+    outdated, packs,
+    platforms::{self, ApiClient},
+    port,
+    progress::{self, PhaseTimer, PhaseTiming, Progress, ProgressSender},
+    report, roundtrip,
+};
+use std::{collections::BTreeMap, fs::File, io::IsTerminal, path::Path};
+use tokio::time::Instant;
+use tracing::{Level, event};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install().wrap_err("Err while initializing color_eyre")?;
     dotenv::dotenv().wrap_err("Error while parsing .env")?;
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_max_level(
             match std::env::var("LOGLEVEL")
                 .unwrap_or("undefined".to_string())
@@ -40,157 +37,249 @@ async fn main() -> Result<()> {
         .init();
 
     let st = Instant::now();
-    let write_type = match std::env::var("OUTPUT_FORMAT")
-        .unwrap_or("UNSPECIFIED".to_string())
-        .as_str()
-    {
-        "MODRINTH" => OutputFormat::MODRINTH,
-        "CURSE" => OutputFormat::CURSEFORGE,
-        "TECHNIC" => OutputFormat::TECHNICPACK,
-        other => OutputFormat::OTHER(other.to_string()),
-    };
-
-    let mut args = std::env::args();
+    let client =
+        build_client(&std::env::var("CURSE_API_KEY").unwrap_or("NO_KEY_SPECIFIED".to_string()))?;
+    let mut args = std::env::args().peekable();
     args.next(); //skip executable
-    let (_pack_path, _pack_ext, mut pack_reader) = match std::env::var("__DBG_FILE_PATH") {
-        Err(_) => args
-            .next()
-            .map(|s| {
-                let mut splits: Vec<&str> = s.split(".").collect();
-                let ext = splits.pop().unwrap();
-                let path = splits.join(".");
-                (
-                    path.to_string(),
-                    ext.to_string(),
-                    zip::ZipArchive::new(File::open(s).unwrap()).unwrap(),
+    match args.peek().map(String::as_str) {
+        Some("update") => {
+            args.next();
+            let dir = args.next().ok_or(std::io::Error::other(
+                "Usage: multipack update <installed-dir> <new-pack>",
+            ))?;
+            let pack_path = args.next().ok_or(std::io::Error::other(
+                "Usage: multipack update <installed-dir> <new-pack>",
+            ))?;
+            let (sender, renderer) = spawn_progress();
+            let mut pack = packs::read_archive(&mut open_pack(&pack_path)?)?.with_progress(sender);
+            install::update(&mut pack, Path::new(&dir), &client).await?;
+            pack.progress = None;
+            renderer.await?;
+        }
+        Some("diff") => {
+            args.next();
+            let (paths, flags) = split_args(args, &[]);
+            let [old_path, new_path] = &paths[..] else {
+                return Err(std::io::Error::other(
+                    "Usage: multipack diff <old-pack> <new-pack> [--json]",
                 )
-            })
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Failed to open input {}",
-            ))?,
-        Ok(s) => {
-            let mut splits: Vec<&str> = s.split('.').collect();
-            let ext = splits.pop().unwrap();
-            let path = splits.join(".");
-            (
-                path.to_string(),
-                ext.to_string(),
-                zip::ZipArchive::new(File::open(s).unwrap()).unwrap(),
+                .into());
+            };
+            let mut old = packs::read_archive(&mut open_pack(old_path)?)?;
+            let mut new = packs::read_archive(&mut open_pack(new_path)?)?;
+            for pack in [&mut old, &mut new] {
+                pack.resolve_curse(&client).await?;
+                pack.resolve_modrinth(&client).await?;
+            }
+            let changes = diff::diff(&old, &new);
+            if flags.contains_key("json") {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                print!("{}", changes);
+            }
+        }
+        Some("outdated") => {
+            args.next();
+            let (paths, flags) = split_args(args, &["channel"]);
+            let [pack_path] = &paths[..] else {
+                return Err(std::io::Error::other(
+                    "Usage: multipack outdated <pack> [--channel release|beta|alpha] [--apply] [--json]",
+                )
+                .into());
+            };
+            let channel = match flags.get("channel") {
+                Some(Some(value)) => outdated::parse_channel(value)?,
+                _ => platforms::curse::ReleaseType::Release,
+            };
+            let mut archive = open_pack(pack_path)?;
+            let format = packs::detect_format(&archive);
+            let mut pack = packs::read_archive(&mut archive)?;
+            let updates = outdated::find_updates(&pack, &client, channel).await?;
+            if flags.contains_key("json") {
+                println!("{}", serde_json::to_string_pretty(&updates)?);
+            } else if updates.is_empty() {
+                println!("Everything is up to date");
+            } else {
+                for update in &updates {
+                    println!("{}", update);
+                }
+            }
+            if flags.contains_key("apply") && !updates.is_empty() {
+                let write_type = OutputFormat::same_as(format)?;
+                outdated::apply(&mut pack, &updates);
+                let out_path = suffixed_path(pack_path, "updated");
+                write_output(
+                    &mut pack,
+                    write_type,
+                    Some(out_path),
+                    &packs::Compression::default(),
+                    packs::modrinth_pack::DownloadPolicy::default(),
+                    &install::OptionalMods::default(),
+                    &client,
+                )
+                .await?;
+            }
+        }
+        Some("port") => {
+            args.next();
+            let (paths, flags) = split_args(args, &["mc", "loader", "loader-version", "channel"]);
+            let (Some(Some(minecraft)), [pack_path]) = (flags.get("mc"), &paths[..]) else {
+                return Err(std::io::Error::other(
+                    "Usage: multipack port <pack> --mc <version> [--loader <loader>] [--loader-version <version>] [--channel release|beta|alpha] [--json]",
+                )
+                .into());
+            };
+            let channel = match flags.get("channel") {
+                Some(Some(value)) => outdated::parse_channel(value)?,
+                _ => platforms::curse::ReleaseType::Release,
+            };
+            let mut archive = open_pack(pack_path)?;
+            let format = packs::detect_format(&archive);
+            let write_type = OutputFormat::same_as(format)?;
+            let mut pack = packs::read_archive(&mut archive)?;
+            pack.resolve_curse(&client).await?;
+            pack.resolve_modrinth(&client).await?;
+            let target = port::PortTarget::new(
+                &pack,
+                minecraft,
+                flags.get("loader").cloned().flatten().as_deref(),
+                flags.get("loader-version").cloned().flatten().as_deref(),
+            )?;
+            let report = port::port(&pack, &client, target, channel).await?;
+            if flags.contains_key("json") {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report);
+            }
+            port::apply(&mut pack, &report);
+            let out_path = suffixed_path(pack_path, minecraft);
+            write_output(
+                &mut pack,
+                write_type,
+                Some(out_path),
+                &packs::Compression::default(),
+                packs::modrinth_pack::DownloadPolicy::default(),
+                &install::OptionalMods::default(),
+                &client,
             )
+            .await?;
         }
-    };
-
-    let out_writer = match write_type {
-        OutputFormat::CURSEFORGE => {
-            todo!("Curseforge is not yet supported");
+        Some("deps") => {
+            args.next();
+            let (paths, flags) = split_args(args, &[]);
+            let [pack_path] = &paths[..] else {
+                return Err(std::io::Error::other("Usage: multipack deps <pack> [--json]").into());
+            };
+            let mut pack = packs::read_archive(&mut open_pack(pack_path)?)?;
+            pack.resolve_curse(&client).await?;
+            pack.resolve_modrinth(&client).await?;
+            deps::collect(&mut pack, &client).await?;
+            let report = deps::check(&pack);
+            if flags.contains_key("json") {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report);
+            }
+            if !report.is_ok() {
+                return Err(eyre!(
+                    "{} dependency problems found",
+                    report.problem_count()
+                ));
+            }
         }
-        OutputFormat::TECHNICPACK => {
-            todo!("Technic is not yet supported");
+        Some("inspect") => {
+            args.next();
+            let (paths, flags) = split_args(args, &[]);
+            let [path] = &paths[..] else {
+                return Err(
+                    std::io::Error::other("Usage: multipack inspect <pack|jar> [--json]").into(),
+                );
+            };
+            let jars = if path.ends_with(".jar") {
+                BTreeMap::from([(
+                    path.clone(),
+                    jar::inspect(
+                        &std::fs::read(path).wrap_err(format!("Err while reading {}", path))?,
+                    )?,
+                )])
+            } else {
+                jar::inspect_pack(&packs::read_archive(&mut open_pack(path)?)?)
+            };
+            if flags.contains_key("json") {
+                println!("{}", serde_json::to_string_pretty(&jars)?);
+            } else {
+                for (path, mods) in &jars {
+                    println!("{}", path);
+                    if mods.is_empty() {
+                        println!("  no mod metadata");
+                    }
+                    for m in mods {
+                        println!("  {}", m);
+                        for dep in &m.dependencies {
+                            println!(
+                                "    {:?} {} {}",
+                                dep.kind,
+                                dep.mod_id,
+                                dep.version.as_deref().unwrap_or("*")
+                            );
+                        }
+                    }
+                }
+            }
         }
-        OutputFormat::MODRINTH => Arc::new(Mutex::new(ZipWriter::new(
-            File::create("pack.mrpack").wrap_err("Err while attempting to create output zip")?,
-        ))),
-        OutputFormat::OTHER(s) => todo!("Format {} not supported", s),
-    };
-    let mut handles = JoinSet::new();
-    let client = reqwest::Client::builder()
-        .user_agent("crinfarr/multipak @indev (dev@crinfarr.io)")
-        .default_headers((|| {
-            let mut hm = HeaderMap::new();
-            hm.append(
-                "x-api-key",
-                HeaderValue::from_str(
-                    &std::env::var("CURSE_API_KEY").unwrap_or("NO_KEY_SPECIFIED".to_string()),
+        Some("validate") => {
+            args.next();
+            let (paths, flags) = split_args(args, &[]);
+            let [pack_path] = &paths[..] else {
+                return Err(std::io::Error::other(
+                    "Usage: multipack validate <pack.mrpack> [--json]",
                 )
-                .unwrap(),
-            );
-
-            hm
-        })())
-        .build()
-        .wrap_err("Err while building network client")?;
-
-    for index in 0..pack_reader.len() {
-        let file = pack_reader
-            .by_index(index)
-            .wrap_err("Err while reading input zip")?;
-        if file.is_dir() {
-            out_writer
-                .lock()
-                .await
-                .add_directory(file.name(), SimpleFileOptions::default())
-                .wrap_err("Err while copying directory")?;
-            continue;
-        }
-        let f_name = file.name().to_string();
-        match f_name.as_str() {
-            "manifest.json" => {
-                let file = serde_json::from_str::<platforms::curse::PackMeta>(
-                    &file.bytes().map(|b| b.unwrap() as char).collect::<String>(),
-                )
-                .wrap_err("Err while loading curseforge manifest")?;
-                event!(Level::WARN, "CONFIG PARSING IS NYI");
-                let mut mods: Vec<
-                    ModInfo<
-                        crate::platforms::curse::PackModDescription,
-                        crate::platforms::curse::APIFile,
-                        crate::platforms::mod_data::curse_mod_data::CurseDependency
-                    >,
-                > = Vec::default();
-                for mod_desc in file.files {
-                    let mod_info: ModInfo<
-                        crate::platforms::curse::PackModDescription,
-                        crate::platforms::curse::APIFile,
-                        crate::platforms::mod_data::curse_mod_data::CurseDependency
-                    > = ModInfo::from(mod_desc).with_shared_client(client.clone());
-                    mods.push(mod_info);
+                .into());
+            };
+            let problems = packs::modrinth_pack::validate_archive(&mut open_pack(pack_path)?)?;
+            if flags.contains_key("json") {
+                println!("{}", serde_json::to_string_pretty(&problems)?);
+            } else {
+                for problem in &problems {
+                    println!("{}", problem);
                 }
-                for mut mod_info in mods {
-                    mod_info.resolve_remotes().await?;
-                    event!(Level::DEBUG, "{:#?}", mod_info);
+            }
+            if !problems.is_empty() {
+                return Err(eyre!("{} problems found in {}", problems.len(), pack_path));
+            }
+        }
+        Some("roundtrip") => {
+            args.next();
+            let (paths, flags) = split_args(args, &["via"]);
+            let [pack_path] = &paths[..] else {
+                return Err(std::io::Error::other(
+                    "Usage: multipack roundtrip <pack> [--via MODRINTH|CURSE|ATLAUNCHER|GDLAUNCHER] [--json]",
+                )
+                .into());
+            };
+            let mut archive = open_pack(pack_path)?;
+            let via = match flags.get("via") {
+                Some(Some(format)) => OutputFormat::from(format.as_str()),
+                _ if packs::detect_format(&archive) == Some(packs::PackFormat::Modrinth) => {
+                    OutputFormat::CURSEFORGE
                 }
-                continue;
+                _ => OutputFormat::MODRINTH,
+            };
+            let report =
+                roundtrip::roundtrip(&mut archive, &via, &packs::Compression::default(), &client)
+                    .await?;
+            if flags.contains_key("json") {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report);
             }
-
-            other => {
-                let out_ref = out_writer.clone();
-                let filename = other.to_string();
-                event!(Level::TRACE, "Vectorizing {}", filename);
-                let f_bytes: Vec<u8> = file.bytes().map(|f| f.unwrap().clone()).collect();
-                event!(Level::TRACE, "Spawning write thread for {}", filename);
-                handles.spawn(
-                    async move {
-                        event!(Level::TRACE, "Waiting on write mutex");
-                        let mut output = out_ref.lock().await;
-                        event!(Level::TRACE, "Got permit");
-                        let mut start_at: u64 = 0;
-                        output
-                            .start_file(filename, SimpleFileOptions::default())
-                            .unwrap();
-                        loop {
-                            let written = output.write(&f_bytes[(start_at as usize)..]).unwrap();
-                            if written == 0 {
-                                break;
-                            } else {
-                                start_at += written as u64;
-                            }
-                        }
-                        event!(Level::TRACE, "Wrote {:?}b", start_at);
-                    }
-                    .instrument(span!(
-                        Level::DEBUG,
-                        "Writer Thread",
-                        file = other
-                    )),
-                );
+            if !report.is_lossless() {
+                return Err(eyre!("Roundtrip through {} is lossy", report.via));
             }
         }
+        _ => convert(args, &client).await?,
     }
 
-    event!(Level::INFO, "All writer threads spawned");
-    handles.join_all().await;
-
     event!(
         Level::INFO,
         "Done in {:?}",
@@ -198,3 +287,301 @@ async fn main() -> Result<()> {
     );
     Ok(())
 }
+
+/**
+ * Converts the pack named by the first positional argument into `OUTPUT_FORMAT`, writing it to the second
+ */
+async fn convert(args: impl Iterator<Item = String>, client: &ApiClient) -> Result<()> {
+    let write_type = OutputFormat::from(
+        std::env::var("OUTPUT_FORMAT")
+            .unwrap_or("UNSPECIFIED".to_string())
+            .as_str(),
+    );
+    let (paths, flags) = split_args(
+        args,
+        &[
+            "duplicates",
+            "report",
+            "compression",
+            "compression-level",
+            "name",
+            "version",
+            "summary",
+            "optional",
+            "disallowed-downloads",
+        ],
+    );
+    let mut paths = paths.into_iter();
+    let mut opts = ConvertOptions::new(write_type.clone(), client.clone());
+    if let Some(Some(value)) = flags.get("duplicates") {
+        opts.duplicates = dedupe::DuplicatePolicy::parse(value)?;
+    }
+    if let Some(Some(value)) = flags.get("disallowed-downloads") {
+        opts.downloads = packs::modrinth_pack::DownloadPolicy::parse(value)?;
+    }
+    if let Some(Some(method)) = flags.get("compression") {
+        opts.compression.method = packs::CompressionMethod::parse(method)?;
+    }
+    if let Some(Some(level)) = flags.get("compression-level") {
+        opts.compression.level = Some(
+            level
+                .parse()
+                .wrap_err(format!("Invalid compression level {}", level))?,
+        );
+    }
+    if flags.contains_key("compress-jars") {
+        opts.compression.store_archives = false;
+    }
+    opts.compression.check()?;
+    opts.metadata = packs::MetadataOverrides {
+        name: flags.get("name").cloned().flatten(),
+        version: flags.get("version").cloned().flatten(),
+        summary: flags.get("summary").cloned().flatten(),
+    };
+
+    let optional = match flags.get("optional") {
+        Some(Some(value)) => install::OptionalMods::parse(value),
+        _ => install::OptionalMods::from_env(),
+    };
+
+    let pack_path = match std::env::var("__DBG_FILE_PATH") {
+        Ok(path) => path,
+        Err(_) => paths.next().ok_or(eyre!(
+            "Err while reading arguments: Usage: multipack <pack> [output] [--duplicates keep-newest|keep-manifest|error] [--disallowed-downloads embed|error] [--compression store|deflate|zstd] [--compression-level <level>] [--compress-jars] [--name <name>] [--version <version>] [--summary <summary>] [--optional all|none|<mods>] [--report <file>]"
+        ))?,
+    };
+    let input = File::open(&pack_path).wrap_err(format!("Err while opening {}", pack_path))?;
+
+    let (sender, renderer) = spawn_progress();
+    opts.progress = Some(sender);
+    let mut conversion = Conversion::read(input, &opts)?;
+    let result = async {
+        conversion.prepare(&opts).await?;
+        write_output(
+            &mut conversion.pack,
+            write_type.clone(),
+            paths.next(),
+            &opts.compression,
+            opts.downloads,
+            &optional,
+            client,
+        )
+        .await
+    }
+    .await;
+    // every sender has to be gone for the renderer to finish
+    opts.progress = None;
+    conversion.pack.progress = None;
+    let timings = renderer.await?;
+    if let Some(Some(report_path)) = flags.get("report") {
+        let report = report::ConversionReport::new(
+            &conversion.pack,
+            &write_type,
+            conversion.duplicates,
+            timings,
+            result.as_ref().err().map(|e| format!("{:#}", e)),
+        );
+        std::fs::write(report_path, serde_json::to_vec_pretty(&report)?)
+            .wrap_err(format!("Err while writing {}", report_path))?;
+    }
+    result
+}
+
+/**
+ * Resolves whatever `write_type` needs and writes the pack to `out_path`, or a default name when [None]
+ *
+ * `optional` only matters for [OutputFormat::DIRECTORY], archives keep every optional entry
+ */
+async fn write_output(
+    pack: &mut packs::Pack,
+    write_type: OutputFormat,
+    out_path: Option<String>,
+    compression: &packs::Compression,
+    downloads: packs::modrinth_pack::DownloadPolicy,
+    optional: &install::OptionalMods,
+    client: &ApiClient,
+) -> Result<()> {
+    let default_path = match write_type {
+        OutputFormat::DIRECTORY => {
+            let side = install::Side::from_env()?;
+            pack.resolve_curse(client).await?;
+            pack.fetch_contents(client, |entry| {
+                side.wants(entry) && optional.wants(entry, side)
+            })
+            .await?;
+            let out_dir = out_path.unwrap_or("pack".to_string());
+            install::install(pack, Path::new(&out_dir), side, optional)?;
+            return Ok(());
+        }
+        OutputFormat::MODRINTH => "pack.mrpack",
+        OutputFormat::ATLAUNCHER => "pack.atlauncher.zip",
+        OutputFormat::GDLAUNCHER => "pack.gdlauncher.zip",
+        _ => "pack.zip",
+    };
+    // written to memory first so an unsupported format leaves no empty file behind
+    let mut out = std::io::Cursor::new(Vec::new());
+    multipack::write_pack(pack, &write_type, &mut out, compression, downloads, client).await?;
+    std::fs::write(
+        out_path.unwrap_or(default_path.to_string()),
+        out.into_inner(),
+    )
+    .wrap_err("Err while attempting to create output zip")
+}
+
+/**
+ * Starts rendering progress events, as bars when stderr (where indicatif draws) is a terminal and as log lines otherwise
+ *
+ * The returned task ends once every sender is dropped, yielding how long each phase took
+ */
+fn spawn_progress() -> (ProgressSender, tokio::task::JoinHandle<Vec<PhaseTiming>>) {
+    let (sender, mut receiver) = progress::channel();
+    let tty = std::io::stderr().is_terminal();
+    let renderer = tokio::spawn(async move {
+        let mut bar: Option<(String, ProgressBar)> = None;
+        let mut last_file = None;
+        let mut timer = PhaseTimer::default();
+        while let Some(event) = receiver.recv().await {
+            timer.observe(&event);
+            if tty {
+                render_bar(&mut bar, event);
+            } else {
+                log_progress(&mut last_file, event);
+            }
+        }
+        if let Some((_, bar)) = bar {
+            bar.finish_and_clear();
+        }
+        timer.finish()
+    });
+    (sender, renderer)
+}
+
+/**
+ * Updates the bar for the current phase, replacing it when the phase changes
+ */
+fn render_bar(bar: &mut Option<(String, ProgressBar)>, event: Progress) {
+    let phase = match &event {
+        Progress::Resolving { platform, .. } => format!("Resolving on {}", platform),
+        Progress::Downloading { .. } => "Downloading".to_string(),
+        Progress::Reading => "Reading".to_string(),
+        Progress::Writing => "Writing".to_string(),
+        Progress::Done => {
+            if let Some((_, old)) = bar.take() {
+                old.finish_and_clear();
+            }
+            return;
+        }
+    };
+    if bar.as_ref().is_none_or(|(current, _)| *current != phase) {
+        if let Some((_, old)) = bar.take() {
+            old.finish_and_clear();
+        }
+        let new = match &event {
+            Progress::Resolving { total, .. } => ProgressBar::new(*total as u64).with_style(
+                ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} eta {eta}")
+                    .unwrap()
+                    .progress_chars("=> "),
+            ),
+            Progress::Downloading { total_bytes, .. } => ProgressBar::new(*total_bytes).with_style(
+                ProgressStyle::with_template(
+                    "{msg} [{bar:40}] {bytes}/{total_bytes} {bytes_per_sec} eta {eta}",
+                )
+                .unwrap()
+                .progress_chars("=> "),
+            ),
+            _ => ProgressBar::new_spinner(),
+        };
+        new.enable_steady_tick(std::time::Duration::from_millis(100));
+        new.set_message(phase.clone());
+        *bar = Some((phase.clone(), new));
+    }
+    let Some((_, bar)) = bar else {
+        return;
+    };
+    match event {
+        Progress::Resolving { done, .. } => bar.set_position(done as u64),
+        Progress::Downloading {
+            name,
+            file,
+            files,
+            bytes,
+            total_bytes,
+        } => {
+            bar.set_length(total_bytes.max(bytes));
+            bar.set_position(bytes);
+            bar.set_message(format!("{} ({}/{})", name, file + 1, files));
+        }
+        _ => {}
+    }
+}
+
+/**
+ * Logs phase changes, every tenth of a resolve and every downloaded file
+ */
+fn log_progress(last_file: &mut Option<usize>, event: Progress) {
+    match event {
+        Progress::Reading => event!(Level::INFO, "Reading pack"),
+        Progress::Writing => event!(Level::INFO, "Writing pack"),
+        Progress::Done => {}
+        Progress::Resolving {
+            platform,
+            done,
+            total,
+        } => {
+            let step = (total / 10).max(1);
+            if done % step == 0 || done == total {
+                event!(Level::INFO, "Resolving on {}: {}/{}", platform, done, total);
+            }
+        }
+        Progress::Downloading {
+            name, file, files, ..
+        } => {
+            if *last_file != Some(file) {
+                event!(Level::INFO, "Downloading {} ({}/{})", name, file + 1, files);
+                *last_file = Some(file);
+            }
+        }
+    }
+}
+
+/**
+ * `pack.zip` -> `pack.{suffix}.zip`
+ */
+fn suffixed_path(path: &str, suffix: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}.{}.{}", stem, suffix, ext),
+        None => format!("{}.{}", path, suffix),
+    }
+}
+
+/**
+ * Splits arguments into positionals and `--flag [value]` pairs, `valued` lists the flags that take a value
+ */
+fn split_args(
+    args: impl Iterator<Item = String>,
+    valued: &[&str],
+) -> (Vec<String>, BTreeMap<String, Option<String>>) {
+    let mut positional = Vec::new();
+    let mut flags = BTreeMap::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some(flag) if valued.contains(&flag) => {
+                flags.insert(flag.to_string(), args.next());
+            }
+            Some(flag) => {
+                flags.insert(flag.to_string(), None);
+            }
+            None => positional.push(arg),
+        }
+    }
+    (positional, flags)
+}
+
+/**
+ * Opens a pack archive from disk
+ */
+fn open_pack(path: &str) -> Result<zip::ZipArchive<File>> {
+    zip::ZipArchive::new(File::open(path).wrap_err(format!("Err while opening {}", path))?)
+        .wrap_err(format!("Err while reading {}", path))
+}
@@ -0,0 +1,312 @@
+use std::fmt::Display;
+
+use color_eyre::{Result, eyre::eyre};
+use serde::Serialize;
+use tracing::{Level, event};
+
+use crate::{
+    diff::EntrySummary,
+    packs::{CurseRef, ModrinthRef, Pack, PackEntry, loader_name},
+    platforms::{
+        ApiClient,
+        curse::{self, CurseModLoaderType, FileIndex, ReleaseType},
+        mr::{self, VersionFileResponse},
+    },
+};
+
+/**
+ * Newer file found for a [PackEntry]
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct Update {
+    /// Index of the entry in [Pack::entries]
+    pub index: usize,
+    pub current: EntrySummary,
+    pub latest: EntrySummary,
+    #[serde(skip)]
+    pub(crate) target: UpdateTarget,
+}
+/**
+ * Where an [Update] points to
+ */
+#[derive(Debug, Clone)]
+pub(crate) enum UpdateTarget {
+    Curse(CurseRef),
+    Modrinth(Box<VersionFileResponse>),
+}
+
+/**
+ * Reads the minimum stability to accept from a `release`/`beta`/`alpha` flag value
+ */
+pub fn parse_channel(value: &str) -> Result<ReleaseType> {
+    match value.to_lowercase().as_str() {
+        "release" => Ok(ReleaseType::Release),
+        "beta" => Ok(ReleaseType::Beta),
+        "alpha" => Ok(ReleaseType::Alpha),
+        other => Err(eyre!("Unknown release channel {}", other)),
+    }
+}
+/**
+ * Maps a modrinth `version_type` onto the curse [ReleaseType]
+ */
+pub fn modrinth_release_type(version_type: &str) -> ReleaseType {
+    match version_type {
+        "beta" => ReleaseType::Beta,
+        "alpha" => ReleaseType::Alpha,
+        _ => ReleaseType::Release,
+    }
+}
+/**
+ * Curse loader type matching a modrinth loader key
+ */
+pub fn curse_loader_type(key: &str) -> CurseModLoaderType {
+    match loader_name(key) {
+        "forge" => CurseModLoaderType::Forge,
+        "neoforge" => CurseModLoaderType::NeoForge,
+        "fabric" => CurseModLoaderType::Fabric,
+        "quilt" => CurseModLoaderType::Quilt,
+        _ => CurseModLoaderType::Any,
+    }
+}
+
+/**
+ * Newest curse file index for `game_version` and `loader` no less stable than `channel`
+ */
+pub fn newest_curse_index<'a>(
+    indexes: &'a [FileIndex],
+    game_version: &str,
+    loader: Option<&CurseModLoaderType>,
+    channel: ReleaseType,
+) -> Option<&'a FileIndex> {
+    indexes
+        .iter()
+        .filter(|i| i.game_version == game_version)
+        .filter(|i| i.release_type <= channel)
+        .filter(|i| match (loader, &i.mod_loader) {
+            (None, _) | (_, None) | (_, Some(CurseModLoaderType::Any)) => true,
+            (Some(wanted), Some(have)) => wanted == have,
+        })
+        .max_by_key(|i| i.file_id)
+}
+/**
+ * Newest modrinth version no less stable than `channel`, `versions` must be sorted newest first
+ */
+pub fn newest_modrinth_version(
+    versions: &[VersionFileResponse],
+    channel: ReleaseType,
+) -> Option<&VersionFileResponse> {
+    versions
+        .iter()
+        .find(|v| modrinth_release_type(&v.version_type) <= channel)
+}
+
+/**
+ * A file an entry could move to, with a name to show for it
+ */
+pub(crate) type Candidate = (UpdateTarget, String);
+
+/**
+ * Newest modrinth version of `project_id` for `minecraft` and `loader_key`
+ */
+pub(crate) async fn modrinth_candidate(
+    client: &ApiClient,
+    project_id: &str,
+    minecraft: &str,
+    loader_key: Option<&str>,
+    channel: ReleaseType,
+) -> Result<Option<Candidate>> {
+    let loaders: Vec<&str> = loader_key.map(loader_name).into_iter().collect();
+    let versions = mr::project_versions(client, project_id, &loaders, &[minecraft]).await?;
+    Ok(newest_modrinth_version(&versions, channel)
+        .map(|v| (UpdateTarget::Modrinth(Box::new(v.clone())), v.name.clone())))
+}
+/**
+ * Newest curse file of `project_id` for `minecraft` and `loader_key`, checking the file indexes first and the latest
+ * files' sortable game versions second
+ */
+pub(crate) async fn curse_candidate(
+    client: &ApiClient,
+    project_id: u32,
+    minecraft: &str,
+    loader_key: Option<&str>,
+    channel: ReleaseType,
+) -> Result<Option<Candidate>> {
+    let data = curse::lookup_mod(client, project_id).await?;
+    let loader = loader_key.map(curse_loader_type);
+    let found = match newest_curse_index(
+        &data.latest_file_indexes,
+        minecraft,
+        loader.as_ref(),
+        channel,
+    ) {
+        Some(index) => Some((index.file_id, index.filename.clone())),
+        None => data
+            .latest_files
+            .iter()
+            .filter(|f| f.release_type <= channel)
+            .filter(|f| {
+                f.sortable_game_versions
+                    .iter()
+                    .any(|v| v.game_version == minecraft)
+            })
+            .filter(|f| match loader_key {
+                Some(key) => f
+                    .game_versions
+                    .iter()
+                    .any(|v| v.eq_ignore_ascii_case(loader_name(key))),
+                None => true,
+            })
+            .max_by_key(|f| f.id)
+            .map(|f| (f.id, f.file_name.clone())),
+    };
+    Ok(found.map(|(file_id, file_name)| {
+        (
+            UpdateTarget::Curse(CurseRef {
+                project_id,
+                file_id,
+            }),
+            file_name,
+        )
+    }))
+}
+
+/**
+ * Builds the [Update] moving `entry` to `candidate`
+ */
+pub(crate) fn make_update(index: usize, entry: &PackEntry, candidate: Candidate) -> Update {
+    let (target, file_name) = candidate;
+    let mut latest = entry.clone();
+    retarget(&mut latest, &target);
+    latest.display_name.get_or_insert(file_name);
+    Update {
+        index,
+        current: entry.into(),
+        latest: (&latest).into(),
+        target,
+    }
+}
+
+/**
+ * Finds the newest compatible file for every entry, entries with a modrinth id are checked on modrinth, the rest on
+ * curseforge
+ */
+pub async fn find_updates(
+    pack: &Pack,
+    client: &ApiClient,
+    channel: ReleaseType,
+) -> Result<Vec<Update>> {
+    let loader_key = pack.loaders.keys().next().map(String::as_str);
+    let mut updates = Vec::new();
+    for (index, entry) in pack.entries.iter().enumerate() {
+        let found = match (&entry.modrinth, &entry.curse) {
+            (Some(mref), _) => match modrinth_candidate(
+                client,
+                &mref.project_id,
+                &pack.minecraft,
+                loader_key,
+                channel,
+            )
+            .await?
+            {
+                Some((UpdateTarget::Modrinth(v), name)) if v.id != mref.version_id => {
+                    newer_than_installed(client, &mref.version_id, &v)
+                        .await?
+                        .then_some((UpdateTarget::Modrinth(v), name))
+                }
+                _ => None,
+            },
+            (None, Some(cref)) => curse_candidate(
+                client,
+                cref.project_id,
+                &pack.minecraft,
+                loader_key,
+                channel,
+            )
+            .await?
+            .filter(
+                |(target, _)| matches!(target, UpdateTarget::Curse(c) if c.file_id > cref.file_id),
+            ),
+            (None, None) => {
+                event!(
+                    Level::DEBUG,
+                    "{} has no project id, skipping",
+                    entry.label()
+                );
+                None
+            }
+        };
+        if let Some(candidate) = found {
+            updates.push(make_update(index, entry, candidate));
+        }
+    }
+    Ok(updates)
+}
+
+/**
+ * Whether `candidate` was published after the installed version, so a newer beta is not "updated" to an older release
+ *
+ * Modrinth timestamps are all UTC RFC 3339 in the same precision, so they order as strings
+ */
+async fn newer_than_installed(
+    client: &ApiClient,
+    installed_id: &str,
+    candidate: &VersionFileResponse,
+) -> Result<bool> {
+    let installed = mr::lookup_version(client, installed_id).await?;
+    Ok(candidate.date_published > installed.date_published)
+}
+
+/**
+ * Points every updated entry at its new file, curseforge entries need [Pack::resolve_curse] afterwards
+ */
+pub fn apply(pack: &mut Pack, updates: &[Update]) {
+    for update in updates {
+        retarget(&mut pack.entries[update.index], &update.target);
+    }
+}
+
+/**
+ * Replaces the file an entry refers to, keeping only what still holds for the new file
+ */
+pub(crate) fn retarget(entry: &mut PackEntry, target: &UpdateTarget) {
+    let folder = entry
+        .path
+        .rsplit_once('/')
+        .map(|(f, _)| f.to_string())
+        .unwrap_or("mods".to_string());
+    *entry = PackEntry {
+        env: entry.env.clone(),
+        required: entry.required,
+        ..Default::default()
+    };
+    match target {
+        UpdateTarget::Curse(cref) => entry.curse = Some(cref.clone()),
+        UpdateTarget::Modrinth(version) => {
+            let Some(file) = version
+                .files
+                .iter()
+                .find(|f| f.primary)
+                .or(version.files.first())
+            else {
+                return;
+            };
+            entry.path = format!("{}/{}", folder, file.filename);
+            entry.display_name = Some(version.name.clone());
+            entry.version_number = Some(version.version_number.clone());
+            entry.sha1 = Some(file.hashes.sha1.clone());
+            entry.sha512 = Some(file.hashes.sha512.clone());
+            entry.file_size = Some(file.size as u64);
+            entry.downloads = vec![file.url.clone()];
+            entry.modrinth = Some(ModrinthRef {
+                project_id: version.project_id.clone(),
+                version_id: version.id.clone(),
+            });
+        }
+    }
+}
+
+impl Display for Update {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {}", self.current, self.latest)
+    }
+}
@@ -0,0 +1,1092 @@
+pub mod atlauncher_pack;
+pub mod curse_pack;
+pub mod gdlauncher_pack;
+pub mod modrinth_pack;
+
+use std::{
+    collections::BTreeMap,
+    io::{Read, Seek, Write},
+    sync::OnceLock,
+};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
+};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use sha2::Sha512;
+use tracing::{Level, event};
+use zip::{DateTime, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::platforms::{
+    ApiClient,
+    curse::{self, APIFile, CurseDependency, RelationType},
+    mod_data::ModInfo,
+    mr::{self, ModrinthEnvironmentRequirement, VersionFileResponse},
+};
+use crate::progress::{self, Progress, ProgressSender};
+
+/**
+ * Format agnostic view of a modpack, every reader produces one and every writer consumes one
+ */
+#[derive(Default, Debug, Clone)]
+#[allow(unused)]
+pub struct Pack {
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub summary: String,
+    pub minecraft: String,
+    /// Modloader versions keyed like modrinth `dependencies` (`forge`, `neoforge`, `fabric-loader`, `quilt-loader`)
+    pub loaders: BTreeMap<String, String>,
+    pub entries: Vec<PackEntry>,
+    pub overrides: Overrides,
+    /// Folder a curseforge manifest keeps its overrides in when it is not `overrides`
+    pub overrides_folder: Option<String>,
+    /// Where resolving and downloading report to, see [Pack::with_progress]
+    pub progress: Option<ProgressSender>,
+}
+/**
+ * Files copied verbatim into the game directory, keyed by their path relative to it
+ */
+#[derive(Default, Debug, Clone)]
+pub struct Overrides {
+    pub common: BTreeMap<String, Vec<u8>>,
+    pub client: BTreeMap<String, Vec<u8>>,
+    pub server: BTreeMap<String, Vec<u8>>,
+}
+/**
+ * A downloadable file in a [Pack]
+ */
+#[derive(Default, Debug, Clone)]
+pub struct PackEntry {
+    /// Path relative to the game directory (`mods/foo.jar`), empty until the file name is known
+    pub path: String,
+    pub display_name: Option<String>,
+    pub version_number: Option<String>,
+    pub sha1: Option<String>,
+    pub sha512: Option<String>,
+    pub file_size: Option<u64>,
+    pub downloads: Vec<String>,
+    pub env: Option<ModrinthEnvironmentRequirement>,
+    pub required: bool,
+    pub curse: Option<CurseRef>,
+    pub modrinth: Option<ModrinthRef>,
+    /// Projects this file relates to, [None] until looked up on curseforge or modrinth
+    pub dependencies: Option<Vec<Dependency>>,
+    /// File content, only filled for formats that carry the jar itself or after [Pack::fetch_contents]
+    pub contents: Option<Vec<u8>>,
+    /// Problems found while converting this entry, see [crate::report]
+    pub warnings: Vec<String>,
+}
+/**
+ * Curseforge identity of a [PackEntry]
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CurseRef {
+    pub project_id: u32,
+    pub file_id: u32,
+}
+/**
+ * Modrinth identity of a [PackEntry]
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModrinthRef {
+    pub project_id: String,
+    pub version_id: String,
+}
+
+/**
+ * Project a [PackEntry] depends on or conflicts with
+ */
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub project: ProjectId,
+    pub kind: DependencyKind,
+}
+/**
+ * Project on either platform
+ */
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectId {
+    Curse(u32),
+    Modrinth(String),
+}
+/**
+ * How a [Dependency] relates to the entry declaring it
+ */
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Required,
+    Optional,
+    Incompatible,
+    /// Shipped inside the declaring file, nothing to install
+    Embedded,
+}
+impl DependencyKind {
+    /**
+     * Kind of a curse [RelationType], tools and unknown relations count as optional
+     */
+    pub fn from_curse(relation: &RelationType) -> Self {
+        match relation {
+            RelationType::RequiredDependency => Self::Required,
+            RelationType::Incompatible => Self::Incompatible,
+            RelationType::EmbeddedLibrary | RelationType::Include => Self::Embedded,
+            RelationType::OptionalDependency | RelationType::Tool | RelationType::Unknown(_) => {
+                Self::Optional
+            }
+        }
+    }
+    /**
+     * Kind of a modrinth `dependency_type`
+     */
+    pub fn from_modrinth(dependency_type: &str) -> Self {
+        match dependency_type {
+            "required" => Self::Required,
+            "incompatible" => Self::Incompatible,
+            "embedded" => Self::Embedded,
+            _ => Self::Optional,
+        }
+    }
+}
+
+/**
+ * What a project is, deciding the folder its files install into
+ */
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectKind {
+    #[default]
+    Mod,
+    ResourcePack,
+    ShaderPack,
+    World,
+    DataPack,
+}
+impl ProjectKind {
+    /**
+     * Kind of a curseforge minecraft class id, anything but the known content classes is a mod
+     */
+    pub fn from_curse_class(class_id: Option<u32>) -> Self {
+        match class_id {
+            Some(12) => Self::ResourcePack,
+            Some(6552) => Self::ShaderPack,
+            Some(17) => Self::World,
+            Some(6945) => Self::DataPack,
+            _ => Self::Mod,
+        }
+    }
+    /**
+     * Kind of a modrinth `project_type`, datapacks may also be `mod` projects with only the `datapack` loader
+     */
+    pub fn from_modrinth(project_type: &str, loaders: &[String]) -> Self {
+        match project_type {
+            "resourcepack" => Self::ResourcePack,
+            "shader" => Self::ShaderPack,
+            "datapack" => Self::DataPack,
+            _ if !loaders.is_empty() && loaders.iter().all(|l| l == "datapack") => Self::DataPack,
+            _ => Self::Mod,
+        }
+    }
+    /**
+     * Folder below the game directory files of this kind go into
+     */
+    pub fn folder(&self) -> &'static str {
+        match self {
+            Self::Mod => "mods",
+            Self::ResourcePack => "resourcepacks",
+            Self::ShaderPack => "shaderpacks",
+            Self::World => "saves",
+            Self::DataPack => "datapacks",
+        }
+    }
+}
+
+impl PackEntry {
+    /**
+     * File name part of [PackEntry::path]
+     */
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or_default()
+    }
+    /**
+     * Display name if known, otherwise the file name
+     */
+    pub fn label(&self) -> String {
+        match (&self.display_name, &self.curse) {
+            (Some(name), _) => name.clone(),
+            (None, _) if !self.path.is_empty() => self.file_name().to_string(),
+            (None, Some(c)) => format!("{}/{}", c.project_id, c.file_id),
+            (None, None) => "<unnamed entry>".to_string(),
+        }
+    }
+    /**
+     * Whether the entry has everything a modrinth index needs to reference it
+     */
+    pub fn is_modrinth_hosted(&self) -> bool {
+        self.sha1.is_some() && self.sha512.is_some() && !self.downloads.is_empty()
+    }
+}
+
+impl Pack {
+    /**
+     * Reports resolving and download progress of this pack into `sender`
+     */
+    pub fn with_progress(mut self, sender: ProgressSender) -> Self {
+        self.progress = Some(sender);
+        self
+    }
+    /**
+     * Fails on the overrides folder, or the first entry or override, whose path is not [is_safe_path], names come from
+     * untrusted manifests and api responses
+     */
+    pub fn check_paths(&self) -> Result<()> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|e| &e.path)
+            .filter(|p| !p.is_empty());
+        let overrides = [
+            &self.overrides.common,
+            &self.overrides.client,
+            &self.overrides.server,
+        ]
+        .into_iter()
+        .flat_map(|o| o.keys());
+        if let Some(folder) = &self.overrides_folder
+            && (!is_safe_path(folder) || folder.contains('/'))
+        {
+            return Err(eyre!("overrides folder {} escapes the pack folder", folder));
+        }
+        match entries.chain(overrides).find(|p| !is_safe_path(p)) {
+            Some(path) => Err(eyre!("{} escapes the pack folder", path)),
+            None => Ok(()),
+        }
+    }
+    /**
+     * Fills file names, hashes and download urls of curseforge entries from the curse api
+     */
+    pub async fn resolve_curse(&mut self, client: &ApiClient) -> Result<()> {
+        let pending = |e: &PackEntry| e.curse.is_some() && (e.path.is_empty() || e.sha1.is_none());
+        let total = self.entries.iter().filter(|e| pending(e)).count();
+        for (done, entry) in self.entries.iter_mut().filter(|e| pending(e)).enumerate() {
+            progress::report(
+                &self.progress,
+                Progress::Resolving {
+                    platform: "curseforge".to_string(),
+                    done,
+                    total,
+                },
+            );
+            let Some(cref) = entry.curse.clone() else {
+                continue;
+            };
+            let mut mod_info: ModInfo<curse::PackModDescription, APIFile, CurseDependency> =
+                ModInfo::from(curse::PackModDescription {
+                    project_id: cref.project_id,
+                    file_id: cref.file_id,
+                    required: entry.required,
+                })
+                .with_shared_client(client.clone());
+            mod_info.resolve_remotes().await.wrap_err(format!(
+                "Err while resolving curseforge file {}/{}",
+                cref.project_id, cref.file_id
+            ))?;
+            let file = mod_info
+                .resolved_info()
+                .ok_or(eyre!("Curse file was not resolved"))?;
+            event!(
+                Level::DEBUG,
+                "Resolved {} from curseforge",
+                file.display_name
+            );
+            if entry.path.is_empty() {
+                let project =
+                    curse::lookup_mod(client, cref.project_id)
+                        .await
+                        .wrap_err(format!(
+                            "Err while looking up curseforge project {}",
+                            cref.project_id
+                        ))?;
+                let kind = ProjectKind::from_curse_class(project.class_id);
+                entry.path = format!("{}/{}", kind.folder(), file.file_name);
+            }
+            entry.display_name.get_or_insert(file.display_name.clone());
+            entry.sha1 = mod_info.sha1.clone();
+            entry.file_size = Some(file.file_length as u64);
+            entry.dependencies = mod_info.deps.as_deref().map(curse_dependencies);
+            match &file.download_url {
+                Some(url) if !entry.downloads.contains(url) => entry.downloads.push(url.clone()),
+                Some(_) => {}
+                None => entry
+                    .warnings
+                    .push("Curseforge does not allow third party distribution".to_string()),
+            }
+        }
+        if total > 0 {
+            progress::report(
+                &self.progress,
+                Progress::Resolving {
+                    platform: "curseforge".to_string(),
+                    done: total,
+                    total,
+                },
+            );
+        }
+        self.check_paths()
+    }
+    /**
+     * Looks every entry without modrinth data up on modrinth by sha1, entries modrinth does not know are left untouched
+     */
+    pub async fn resolve_modrinth(&mut self, client: &ApiClient) -> Result<()> {
+        let pending = |e: &PackEntry| !(e.modrinth.is_some() && e.is_modrinth_hosted());
+        let total = self.entries.iter().filter(|e| pending(e)).count();
+        for (done, entry) in self.entries.iter_mut().filter(|e| pending(e)).enumerate() {
+            progress::report(
+                &self.progress,
+                Progress::Resolving {
+                    platform: "modrinth".to_string(),
+                    done,
+                    total,
+                },
+            );
+            let version = match (&entry.sha1, &entry.modrinth) {
+                (Some(sha1), _) => mr::lookup_hash(client, sha1).await?,
+                (None, Some(mref)) => Some(mr::lookup_version(client, &mref.version_id).await?),
+                (None, None) => None,
+            };
+            let Some(version) = version else {
+                event!(
+                    Level::WARN,
+                    "{} is not available on modrinth",
+                    entry.label()
+                );
+                entry.warnings.push("Not available on modrinth".to_string());
+                continue;
+            };
+            let file = match &entry.sha1 {
+                Some(sha1) => version.files.iter().find(|f| &f.hashes.sha1 == sha1),
+                None => version
+                    .files
+                    .iter()
+                    .find(|f| f.primary)
+                    .or(version.files.first()),
+            }
+            .ok_or(eyre!(
+                "Modrinth version {} has no matching file",
+                version.id
+            ))?;
+            event!(
+                Level::INFO,
+                "{} found on modrinth at {}",
+                entry.label(),
+                file.url
+            );
+            if entry.path.is_empty() {
+                let project = mr::lookup_project(client, &version.project_id).await?;
+                let kind = ProjectKind::from_modrinth(&project.project_type, &version.loaders);
+                entry.path = format!("{}/{}", kind.folder(), file.filename);
+            }
+            entry.display_name.get_or_insert(version.name.clone());
+            entry.version_number = Some(version.version_number.clone());
+            entry.sha1 = Some(file.hashes.sha1.clone());
+            entry.sha512 = Some(file.hashes.sha512.clone());
+            entry.file_size = Some(file.size as u64);
+            entry.downloads = vec![file.url.clone()];
+            entry.modrinth = Some(ModrinthRef {
+                project_id: version.project_id.clone(),
+                version_id: version.id.clone(),
+            });
+            if entry.dependencies.is_none() {
+                entry.dependencies = Some(modrinth_dependencies(client, &version).await?);
+            }
+        }
+        if total > 0 {
+            progress::report(
+                &self.progress,
+                Progress::Resolving {
+                    platform: "modrinth".to_string(),
+                    done: total,
+                    total,
+                },
+            );
+        }
+        self.check_paths()
+    }
+    /**
+     * Finds the curseforge file of every entry carrying its contents but no curseforge data, by fingerprint
+     *
+     * Gives entries from other platforms their curseforge identity, so they satisfy curseforge dependencies and match
+     * curseforge copies of the same mod
+     */
+    pub async fn resolve_curse_fingerprints(&mut self, client: &ApiClient) -> Result<()> {
+        let pending: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.curse.is_none() && e.contents.is_some())
+            .map(|(i, _)| i)
+            .collect();
+        let files: Vec<&[u8]> = pending
+            .iter()
+            .filter_map(|i| self.entries[*i].contents.as_deref())
+            .collect();
+        let found = curse_files_by_fingerprint(client, &files).await?;
+        for (index, cref) in pending.into_iter().zip(found) {
+            let Some(cref) = cref else {
+                continue;
+            };
+            let entry = &mut self.entries[index];
+            event!(
+                Level::DEBUG,
+                "{} is curseforge file {}/{}",
+                entry.label(),
+                cref.project_id,
+                cref.file_id
+            );
+            entry.curse = Some(cref);
+        }
+        Ok(())
+    }
+    /**
+     * Downloads and sha1 checks every entry matching `filter` that does not carry its contents yet
+     */
+    pub async fn fetch_contents(
+        &mut self,
+        client: &ApiClient,
+        filter: impl Fn(&PackEntry) -> bool,
+    ) -> Result<()> {
+        let wanted = |e: &PackEntry| e.contents.is_none() && filter(e);
+        let files = self.entries.iter().filter(|e| wanted(e)).count();
+        let total_bytes: u64 = self
+            .entries
+            .iter()
+            .filter(|e| wanted(e))
+            .filter_map(|e| e.file_size)
+            .sum();
+        let mut bytes = 0;
+        for (file, entry) in self.entries.iter_mut().filter(|e| wanted(e)).enumerate() {
+            let Some(url) = entry.downloads.first() else {
+                entry.warnings.push("No download url".to_string());
+                return Err(eyre!("No download url available for {}", entry.label()));
+            };
+            event!(Level::DEBUG, "Downloading {} from {}", entry.label(), url);
+            let mut resp = client.http.get(url).send().await?;
+            if !resp.status().is_success() {
+                return Err(eyre!(
+                    "Failed to download {}: Error {}",
+                    entry.label(),
+                    resp.status()
+                ));
+            }
+            // file sizes come from the pack being read, so they only hint the allocation
+            let mut content =
+                Vec::with_capacity(entry.file_size.unwrap_or_default().min(1 << 20) as usize);
+            while let Some(chunk) = resp.chunk().await? {
+                content.extend_from_slice(&chunk);
+                bytes += chunk.len() as u64;
+                progress::report(
+                    &self.progress,
+                    Progress::Downloading {
+                        name: entry.label(),
+                        file,
+                        files,
+                        bytes,
+                        total_bytes,
+                    },
+                );
+            }
+            let hash = sha1_hex(&content);
+            match &entry.sha1 {
+                Some(expected) if *expected != hash => {
+                    let message = format!("Hash mismatch: expected {}, got {}", expected, hash);
+                    entry.warnings.push(message.clone());
+                    return Err(eyre!("{} for {}", message, entry.label()));
+                }
+                Some(_) => {}
+                None => entry.sha1 = Some(hash),
+            }
+            let hash = sha512_hex(&content);
+            match &entry.sha512 {
+                Some(expected) if *expected != hash => {
+                    let message = format!("Sha512 mismatch: expected {}, got {}", expected, hash);
+                    entry.warnings.push(message.clone());
+                    return Err(eyre!("{} for {}", message, entry.label()));
+                }
+                Some(_) => {}
+                // curseforge only knows sha1, modrinth needs both to link a file
+                None => entry.sha512 = Some(hash),
+            }
+            entry.file_size = Some(content.len() as u64);
+            entry.contents = Some(content);
+        }
+        Ok(())
+    }
+}
+
+/**
+ * Formats multipack can read
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackFormat {
+    Modrinth,
+    Curse,
+    ATLauncher,
+    GDLauncher,
+}
+/**
+ * Picks the format of an archive from the metadata file it contains
+ */
+pub fn detect_format<R: Read + Seek>(archive: &ZipArchive<R>) -> Option<PackFormat> {
+    [
+        ("modrinth.index.json", PackFormat::Modrinth),
+        ("manifest.json", PackFormat::Curse),
+        ("instance.json", PackFormat::ATLauncher),
+        ("config.json", PackFormat::GDLauncher),
+    ]
+    .into_iter()
+    .find(|(name, _)| archive.index_for_name(name).is_some())
+    .map(|(_, format)| format)
+}
+/**
+ * Reads any supported pack archive
+ */
+pub fn read_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Pack> {
+    check_archive(archive, &ArchiveLimits::default())?;
+    let mut pack = match detect_format(archive) {
+        Some(PackFormat::Modrinth) => {
+            event!(Level::INFO, "Detected modrinth modpack");
+            modrinth_pack::read(archive)
+        }
+        Some(PackFormat::Curse) => {
+            event!(Level::INFO, "Detected curseforge modpack");
+            curse_pack::read(archive)
+        }
+        Some(PackFormat::ATLauncher) => {
+            event!(Level::INFO, "Detected ATLauncher instance");
+            atlauncher_pack::read(archive)
+        }
+        Some(PackFormat::GDLauncher) => {
+            event!(Level::INFO, "Detected GDLauncher instance");
+            gdlauncher_pack::read(archive)
+        }
+        None => Err(eyre!("Input is not a known modpack format")),
+    }?;
+    if let Some(index) = archive.index_for_name(SIDECAR_NAME) {
+        serde_json::from_slice::<Sidecar>(&read_entry(archive, index)?)
+            .wrap_err(format!("Err while loading {}", SIDECAR_NAME))?
+            .apply(&mut pack);
+        // launcher instance readers pick every loose file up as an override
+        pack.overrides.common.remove(SIDECAR_NAME);
+    }
+    pack.check_paths()?;
+    Ok(pack)
+}
+
+/**
+ * Name of the file carrying pack metadata the output format has no field for
+ */
+pub const SIDECAR_NAME: &str = "multipack.json";
+/**
+ * Pack metadata written next to the manifest when the format cannot hold it, [read_archive] reads it back so round
+ * trips keep it
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Sidecar {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary: String,
+}
+impl Sidecar {
+    /**
+     * Fills the fields of `pack` the format it was read from left empty
+     */
+    pub fn apply(self, pack: &mut Pack) {
+        for (field, value) in [
+            (&mut pack.name, self.name),
+            (&mut pack.version, self.version),
+            (&mut pack.author, self.author),
+            (&mut pack.summary, self.summary),
+        ] {
+            if field.is_empty() {
+                *field = value;
+            }
+        }
+    }
+}
+/**
+ * Writes `sidecar` unless it is empty
+ */
+pub(crate) fn write_sidecar<W: Write + Seek>(
+    out: &mut ZipWriter<W>,
+    sidecar: &Sidecar,
+    compression: &Compression,
+) -> Result<()> {
+    if *sidecar == Sidecar::default() {
+        return Ok(());
+    }
+    write_entry(out, SIDECAR_NAME, &sorted_json(sidecar, true)?, compression)
+}
+
+/**
+ * Metadata replacing what was read from the input
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MetadataOverrides {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub summary: Option<String>,
+}
+impl MetadataOverrides {
+    pub fn apply(&self, pack: &mut Pack) {
+        for (field, value) in [
+            (&mut pack.name, &self.name),
+            (&mut pack.version, &self.version),
+            (&mut pack.summary, &self.summary),
+        ] {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+    }
+}
+
+/**
+ * Bounds on what an input archive may contain, packs come from untrusted uploads
+ */
+#[derive(Debug, Clone)]
+pub struct ArchiveLimits {
+    pub max_entries: usize,
+    /// Sum of the uncompressed size of every entry, in bytes
+    pub max_total_size: u64,
+    /// Largest uncompressed to compressed size ratio of an entry, only checked for entries over 1 MiB
+    pub max_ratio: u64,
+}
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 65_536,
+            max_total_size: 8 << 30,
+            max_ratio: 100,
+        }
+    }
+}
+
+/**
+ * Whether `path` stays inside the folder it is relative to, rejecting `..`, absolute paths and drive letters
+ */
+pub fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with(['/', '\\'])
+        && path.chars().nth(1) != Some(':')
+        && path.split(['/', '\\']).all(|part| part != "..")
+}
+
+/**
+ * Rejects archives with unsafe entry names, symlinks, or more entries or data than `limits` allow, without
+ * decompressing anything
+ */
+pub fn check_archive<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    limits: &ArchiveLimits,
+) -> Result<()> {
+    if archive.len() > limits.max_entries {
+        return Err(eyre!(
+            "Archive has {} entries, more than the limit of {}",
+            archive.len(),
+            limits.max_entries
+        ));
+    }
+    let mut total: u64 = 0;
+    for index in 0..archive.len() {
+        let file = archive
+            .by_index_raw(index)
+            .wrap_err("Err while reading input zip")?;
+        let name = file.name();
+        if !is_safe_path(name) || file.enclosed_name().is_none() {
+            return Err(eyre!("Archive entry {} escapes the pack folder", name));
+        }
+        if file.is_symlink() {
+            return Err(eyre!("Archive entry {} is a symlink", name));
+        }
+        if file.size() > 1 << 20 && file.size() / file.compressed_size().max(1) > limits.max_ratio {
+            return Err(eyre!(
+                "Archive entry {} expands {} bytes to {}, more than {} times",
+                name,
+                file.compressed_size(),
+                file.size(),
+                limits.max_ratio
+            ));
+        }
+        total = total.saturating_add(file.size());
+        if total > limits.max_total_size {
+            return Err(eyre!(
+                "Archive is larger than {} bytes uncompressed, stopped at {}",
+                limits.max_total_size,
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Dependencies of a curse file
+ */
+pub fn curse_dependencies(deps: &[CurseDependency]) -> Vec<Dependency> {
+    deps.iter()
+        .map(|CurseDependency(mod_id, relation)| Dependency {
+            project: ProjectId::Curse(*mod_id),
+            kind: DependencyKind::from_curse(relation),
+        })
+        .collect()
+}
+/**
+ * Dependencies of a modrinth version, looking up the project of dependencies that only name a version
+ */
+pub async fn modrinth_dependencies(
+    client: &ApiClient,
+    version: &VersionFileResponse,
+) -> Result<Vec<Dependency>> {
+    let mut out = Vec::new();
+    for dep in &version.dependencies {
+        let project_id = match (&dep.project_id, &dep.version_id) {
+            (Some(project_id), _) => project_id.clone(),
+            (None, Some(version_id)) => mr::lookup_version(client, version_id).await?.project_id,
+            (None, None) => {
+                event!(
+                    Level::DEBUG,
+                    "Skipping dependency {:?} of {} without a project",
+                    dep.file_name,
+                    version.name
+                );
+                continue;
+            }
+        };
+        out.push(Dependency {
+            project: ProjectId::Modrinth(project_id),
+            kind: DependencyKind::from_modrinth(&dep.dependency_type),
+        });
+    }
+    Ok(out)
+}
+
+/**
+ * Maps a loader name as used by curseforge and the launchers to its modrinth dependency key
+ */
+pub fn loader_key(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().as_str() {
+        "forge" => Some("forge"),
+        "neoforge" => Some("neoforge"),
+        "fabric" | "fabric-loader" => Some("fabric-loader"),
+        "quilt" | "quilt-loader" => Some("quilt-loader"),
+        _ => None,
+    }
+}
+/**
+ * Inverse of [loader_key], gives the short lowercase loader name
+ */
+pub fn loader_name(key: &str) -> &str {
+    key.strip_suffix("-loader").unwrap_or(key)
+}
+
+/**
+ * Lowercase hex sha1 of `data`
+ */
+pub fn sha1_hex(data: &[u8]) -> String {
+    base16ct::lower::encode_string(&Sha1::digest(data))
+}
+/**
+ * Lowercase hex sha512 of `data`
+ */
+pub fn sha512_hex(data: &[u8]) -> String {
+    base16ct::lower::encode_string(&Sha512::digest(data))
+}
+
+/**
+ * Fingerprint the curse api knows files by, murmur2 with seed 1 over the file without whitespace bytes
+ */
+pub fn curse_fingerprint(data: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    let bytes: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|b| !matches!(b, 9 | 10 | 13 | 32))
+        .collect();
+    let mut hash = 1 ^ bytes.len() as u32;
+    let mut chunks = bytes.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]).wrapping_mul(M);
+        k ^= k >> 24;
+        hash = hash.wrapping_mul(M) ^ k.wrapping_mul(M);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, b) in tail.iter().enumerate() {
+            hash ^= (*b as u32) << (8 * i);
+        }
+        hash = hash.wrapping_mul(M);
+    }
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^ (hash >> 15)
+}
+
+/**
+ * Curseforge file of each of `files` by [curse_fingerprint], [None] for files curseforge does not know
+ *
+ * One lookup for all of them, none when `files` is empty
+ */
+pub async fn curse_files_by_fingerprint(
+    client: &ApiClient,
+    files: &[&[u8]],
+) -> Result<Vec<Option<CurseRef>>> {
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fingerprints: Vec<u32> = files.iter().map(|f| curse_fingerprint(f)).collect();
+    let matches = curse::lookup_fingerprints(client, &fingerprints)
+        .await
+        .wrap_err("Err while looking up curseforge fingerprints")?;
+    Ok(fingerprints
+        .iter()
+        .map(|fingerprint| {
+            matches
+                .iter()
+                .find(|m| m.file.file_fingerprint == *fingerprint as i64)
+                .map(|m| CurseRef {
+                    project_id: m.id,
+                    file_id: m.file.id,
+                })
+        })
+        .collect())
+}
+
+/**
+ * Reads a whole archive entry into memory
+ */
+pub(crate) fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    index: usize,
+) -> Result<Vec<u8>> {
+    let mut file = archive
+        .by_index(index)
+        .wrap_err("Err while reading input zip")?;
+    let size = file.size();
+    let name = file.name().to_string();
+    // the header size is untrusted until checked, so it only bounds the read and not the allocation
+    let mut buf = Vec::with_capacity(size.min(1 << 20) as usize);
+    (&mut file).take(size + 1).read_to_end(&mut buf)?;
+    if buf.len() as u64 > size {
+        return Err(eyre!(
+            "Archive entry {} is larger than its header says",
+            name
+        ));
+    }
+    Ok(buf)
+}
+/**
+ * Collects every file below `prefix` in the archive, keyed by its path with the prefix removed
+ */
+pub(crate) fn read_prefixed<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    prefix: &str,
+) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut out = BTreeMap::new();
+    for index in 0..archive.len() {
+        let name = match archive.name_for_index(index) {
+            Some(n) => n.to_string(),
+            None => continue,
+        };
+        if name.ends_with('/') {
+            continue;
+        }
+        if let Some(stripped) = name.strip_prefix(prefix)
+            && !stripped.is_empty()
+        {
+            out.insert(stripped.to_string(), read_entry(archive, index)?);
+        }
+    }
+    Ok(out)
+}
+/**
+ * Timestamp stamped on every written file so rebuilt archives are identical, `SOURCE_DATE_EPOCH` when set, otherwise
+ * the earliest date a zip can hold
+ */
+pub fn archive_time() -> DateTime {
+    static TIME: OnceLock<DateTime> = OnceLock::new();
+    *TIME.get_or_init(|| {
+        let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") else {
+            return DateTime::default();
+        };
+        epoch_to_zip_time(&epoch).unwrap_or_else(|| {
+            event!(
+                Level::WARN,
+                "SOURCE_DATE_EPOCH {} is not a time a zip can hold, using 1980-01-01",
+                epoch
+            );
+            DateTime::default()
+        })
+    })
+}
+/**
+ * Zip timestamp of a `SOURCE_DATE_EPOCH` value (seconds since 1970-01-01 UTC), [None] if it is not a number or falls
+ * outside 1980-2107
+ */
+pub fn epoch_to_zip_time(epoch: &str) -> Option<DateTime> {
+    let secs: i64 = epoch.trim().parse().ok()?;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    let time = secs.rem_euclid(86400);
+    DateTime::from_date_and_time(
+        year.try_into().ok()?,
+        month,
+        day,
+        (time / 3600) as u8,
+        (time % 3600 / 60) as u8,
+        (time % 60) as u8,
+    )
+    .ok()
+}
+/**
+ * Year, month and day of a count of days since 1970-01-01
+ */
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+/**
+ * Serializes `value` with every object's keys sorted, so generated manifests do not depend on field order
+ */
+pub(crate) fn sorted_json(value: &impl Serialize, pretty: bool) -> Result<Vec<u8>> {
+    let value = serde_json::to_value(value)?;
+    Ok(if pretty {
+        serde_json::to_vec_pretty(&value)?
+    } else {
+        serde_json::to_vec(&value)?
+    })
+}
+/**
+ * Compressor used for files in written archives
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CompressionMethod {
+    Store,
+    #[default]
+    Deflate,
+    /// Not every launcher can read it
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+impl CompressionMethod {
+    /**
+     * Reads a `--compression` flag value
+     */
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "store" => Ok(Self::Store),
+            "deflate" => Ok(Self::Deflate),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Self::Zstd),
+            #[cfg(not(feature = "zstd"))]
+            "zstd" => Err(eyre!("multipack was built without the zstd feature")),
+            other => Err(eyre!("Unknown compression {}", other)),
+        }
+    }
+}
+/**
+ * How files are compressed in written archives
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compression {
+    pub method: CompressionMethod,
+    /// Level passed to the compressor, [None] for its default
+    pub level: Option<i64>,
+    /// Store files that already are zip archives, like jars, instead of compressing them twice
+    pub store_archives: bool,
+}
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            method: CompressionMethod::Deflate,
+            level: None,
+            store_archives: true,
+        }
+    }
+}
+impl Compression {
+    /**
+     * Checks the level is one the method accepts, before anything is written
+     */
+    pub fn check(&self) -> Result<()> {
+        let range = match self.method {
+            CompressionMethod::Store => return Ok(()),
+            CompressionMethod::Deflate => 0..=9,
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => -7..=22,
+        };
+        match self.level {
+            Some(level) if !range.contains(&level) => Err(eyre!(
+                "Err while checking compression: level {} is outside {}..={} for {:?}",
+                level,
+                range.start(),
+                range.end(),
+                self.method
+            )),
+            _ => Ok(()),
+        }
+    }
+    /**
+     * Zip options for writing `data` to `path`, with a fixed timestamp and permissions
+     */
+    pub fn file_options(&self, path: &str, data: &[u8]) -> SimpleFileOptions {
+        let is_archive =
+            data.starts_with(b"PK\x03\x04") || path.ends_with(".jar") || path.ends_with(".zip");
+        let method = match self.method {
+            _ if self.store_archives && is_archive => zip::CompressionMethod::Stored,
+            CompressionMethod::Store => zip::CompressionMethod::Stored,
+            CompressionMethod::Deflate => zip::CompressionMethod::Deflated,
+            #[cfg(feature = "zstd")]
+            CompressionMethod::Zstd => zip::CompressionMethod::Zstd,
+        };
+        SimpleFileOptions::default()
+            .compression_method(method)
+            .compression_level(match method {
+                zip::CompressionMethod::Stored => None,
+                _ => self.level,
+            })
+            .last_modified_time(archive_time())
+            .unix_permissions(0o644)
+    }
+}
+/**
+ * Writes `data` as a new file in the output archive
+ */
+pub(crate) fn write_entry<W: Write + Seek>(
+    out: &mut ZipWriter<W>,
+    path: &str,
+    data: &[u8],
+    compression: &Compression,
+) -> Result<()> {
+    out.start_file(path, compression.file_options(path, data))
+        .wrap_err(format!("Err while starting {}", path))?;
+    out.write_all(data)
+        .wrap_err(format!("Err while writing {}", path))?;
+    event!(Level::TRACE, "Wrote {:?}b to {}", data.len(), path);
+    Ok(())
+}
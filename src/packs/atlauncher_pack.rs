@@ -0,0 +1,229 @@
+use std::io::{Read, Seek, Write};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
+};
+use serde_json::Map;
+use zip::{ZipArchive, ZipWriter};
+
+use super::{
+    Compression, CurseRef, ModrinthRef, Pack, PackEntry, Sidecar, loader_key, loader_name,
+    read_entry, read_prefixed, sha1_hex, sorted_json, write_entry, write_sidecar,
+};
+use crate::install::{Side, merged_overrides};
+use crate::platforms::{
+    atl::{
+        InstanceMeta, InstanceMod, LauncherMeta, LoaderVersion, ModrinthProjectRef,
+        ModrinthVersionRef,
+    },
+    mr::{HashInfo, ModrinthFileInfo},
+};
+
+/**
+ * Reads a zipped ATLauncher instance folder into a [Pack]
+ *
+ * Jars listed in `instance.json` become entries carrying their contents, everything else becomes an override
+ */
+pub fn read<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Pack> {
+    let index = archive
+        .index_for_name("instance.json")
+        .ok_or(eyre!("No instance.json in archive"))?;
+    let meta = serde_json::from_slice::<InstanceMeta>(&read_entry(archive, index)?)
+        .wrap_err("Err while loading ATLauncher instance")?;
+    let mut pack = Pack {
+        name: meta.launcher.name,
+        version: meta.launcher.version,
+        summary: meta.launcher.description.unwrap_or_default(),
+        minecraft: meta.id,
+        ..Default::default()
+    };
+    if let Some(loader) = meta.launcher.loader_version {
+        let key = loader_key(&loader.loader_type)
+            .ok_or(eyre!("Unknown modloader {}", loader.loader_type))?;
+        pack.loaders.insert(key.to_string(), loader.version);
+    }
+    let mut files = read_prefixed(archive, "")?;
+    files.remove("instance.json");
+    for instance_mod in meta.launcher.mods.into_iter().filter(|m| !m.disabled) {
+        let path = format!(
+            "{}/{}",
+            type_folder(&instance_mod.mod_type),
+            instance_mod.file
+        );
+        let mut entry = PackEntry {
+            display_name: Some(instance_mod.name),
+            version_number: Some(instance_mod.version).filter(|v| !v.is_empty()),
+            required: !instance_mod.optional,
+            contents: files.remove(&path),
+            ..Default::default()
+        };
+        if let (Some(project_id), Some(file_id)) = (
+            instance_mod.curse_forge_project_id,
+            instance_mod.curse_forge_file_id,
+        ) {
+            entry.curse = Some(CurseRef {
+                project_id,
+                file_id,
+            });
+        }
+        if let Some(version) = instance_mod.modrinth_version {
+            if let Some(file) = version
+                .files
+                .iter()
+                .find(|f| f.filename == instance_mod.file)
+                .or(version.files.first())
+            {
+                entry.sha1 = Some(file.hashes.sha1.clone());
+                entry.sha512 = Some(file.hashes.sha512.clone());
+                entry.file_size = Some(file.size as u64);
+                entry.downloads = vec![file.url.clone()];
+            }
+            entry.modrinth = Some(ModrinthRef {
+                project_id: version.project_id,
+                version_id: version.id,
+            });
+        }
+        if let Some(contents) = &entry.contents {
+            entry.sha1 = Some(sha1_hex(contents));
+            entry.file_size = Some(contents.len() as u64);
+        }
+        entry.path = path;
+        pack.entries.push(entry);
+    }
+    pack.overrides.common = files;
+    Ok(pack)
+}
+
+/**
+ * Writes a [Pack] as a zipped ATLauncher instance folder, every entry must carry its contents
+ */
+pub fn write<W: Write + Seek>(
+    pack: &Pack,
+    out: &mut ZipWriter<W>,
+    compression: &Compression,
+) -> Result<()> {
+    let mut mods = Vec::new();
+    for entry in &pack.entries {
+        let contents = entry
+            .contents
+            .as_ref()
+            .ok_or(eyre!("{} was not downloaded", entry.label()))?;
+        write_entry(out, &entry.path, contents, compression)?;
+        let folder = entry
+            .path
+            .rsplit_once('/')
+            .map(|(f, _)| f)
+            .unwrap_or("mods");
+        // files in folders ATLauncher has no type for stay plain files
+        let Some(mod_type) = folder_type(folder) else {
+            continue;
+        };
+        mods.push(InstanceMod {
+            name: entry.label(),
+            version: entry.version_number.clone().unwrap_or_default(),
+            optional: !entry.required,
+            file: entry.file_name().to_string(),
+            mod_type: mod_type.to_string(),
+            disabled: false,
+            user_added: false,
+            curse_forge_project_id: entry.curse.as_ref().map(|c| c.project_id),
+            curse_forge_file_id: entry.curse.as_ref().map(|c| c.file_id),
+            modrinth_project: entry.modrinth.as_ref().map(|m| ModrinthProjectRef {
+                id: m.project_id.clone(),
+                extra: Map::new(),
+            }),
+            modrinth_version: entry.modrinth.as_ref().map(|m| ModrinthVersionRef {
+                id: m.version_id.clone(),
+                project_id: m.project_id.clone(),
+                files: match (&entry.sha1, &entry.sha512, entry.downloads.first()) {
+                    (Some(sha1), Some(sha512), Some(url)) => vec![ModrinthFileInfo {
+                        hashes: HashInfo {
+                            sha512: sha512.clone(),
+                            sha1: sha1.clone(),
+                        },
+                        url: url.clone(),
+                        filename: entry.file_name().to_string(),
+                        primary: true,
+                        size: entry.file_size.unwrap_or_default() as u32,
+                        file_type: None,
+                    }],
+                    _ => vec![],
+                },
+                extra: Map::new(),
+            }),
+            extra: Map::new(),
+        });
+    }
+    let meta = InstanceMeta {
+        id: pack.minecraft.clone(),
+        launcher: LauncherMeta {
+            name: pack.name.clone(),
+            pack: pack.name.clone(),
+            version: pack.version.clone(),
+            description: Some(pack.summary.clone()).filter(|s| !s.is_empty()),
+            loader_version: pack
+                .loaders
+                .iter()
+                .next()
+                .map(|(key, version)| LoaderVersion {
+                    version: version.clone(),
+                    loader_type: atl_loader_type(key).to_string(),
+                    extra: Map::new(),
+                }),
+            mods,
+            extra: Map::new(),
+        },
+        extra: Map::new(),
+    };
+    write_entry(
+        out,
+        "instance.json",
+        &sorted_json(&meta, true)?,
+        compression,
+    )?;
+    let sidecar = Sidecar {
+        author: pack.author.clone(),
+        ..Default::default()
+    };
+    write_sidecar(out, &sidecar, compression)?;
+    for (path, data) in merged_overrides(pack, Side::Client) {
+        write_entry(out, path, data, compression)?;
+    }
+    Ok(())
+}
+
+/**
+ * Folder an ATLauncher mod `type` installs into
+ */
+fn type_folder(mod_type: &str) -> &'static str {
+    match mod_type {
+        "resourcepack" => "resourcepacks",
+        "shaderpack" => "shaderpacks",
+        "worlds" => "saves",
+        _ => "mods",
+    }
+}
+/**
+ * Inverse of [type_folder], [None] for folders without a type
+ */
+fn folder_type(folder: &str) -> Option<&'static str> {
+    match folder {
+        "mods" => Some("mods"),
+        "resourcepacks" => Some("resourcepack"),
+        "shaderpacks" => Some("shaderpack"),
+        "saves" => Some("worlds"),
+        _ => None,
+    }
+}
+/**
+ * ATLauncher spelling of a modrinth loader key
+ */
+fn atl_loader_type(key: &str) -> &'static str {
+    match loader_name(key) {
+        "neoforge" => "NeoForge",
+        "fabric" => "Fabric",
+        "quilt" => "Quilt",
+        _ => "Forge",
+    }
+}
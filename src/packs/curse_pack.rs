@@ -0,0 +1,211 @@
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    io::{Read, Seek, Write},
+};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
+};
+use tracing::{Level, event};
+use zip::{ZipArchive, ZipWriter};
+
+use super::{
+    Compression, CurseRef, Pack, PackEntry, Sidecar, loader_key, loader_name, read_entry,
+    read_prefixed, sorted_json, write_entry, write_sidecar,
+};
+use crate::install::{Side, merged_overrides};
+use crate::platforms::ApiClient;
+use crate::platforms::curse::{
+    self, APIModData, PackMinecraftMetadata, PackModDescription, PackModLoaderMetadata,
+};
+
+/**
+ * Reads a curseforge modpack zip into a [Pack]
+ */
+pub fn read<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Pack> {
+    let index = archive
+        .index_for_name("manifest.json")
+        .ok_or(eyre!("No manifest.json in archive"))?;
+    let meta = serde_json::from_slice::<curse::PackMeta>(&read_entry(archive, index)?)
+        .wrap_err("Err while loading curseforge manifest")?;
+    let mut pack = Pack {
+        name: meta.name,
+        version: meta.version,
+        author: meta.author,
+        minecraft: meta.minecraft.version,
+        ..Default::default()
+    };
+    for loader in &meta.minecraft.mod_loaders {
+        let (name, version) = split_loader_id(&loader.id)?;
+        pack.loaders.insert(name, version);
+    }
+    pack.entries = meta
+        .files
+        .into_iter()
+        .map(|file| PackEntry {
+            curse: Some(CurseRef {
+                project_id: file.project_id,
+                file_id: file.file_id,
+            }),
+            required: file.required,
+            ..Default::default()
+        })
+        .collect();
+    pack.overrides.common = read_prefixed(archive, &format!("{}/", meta.overrides))?;
+    pack.overrides_folder = Some(meta.overrides).filter(|folder| folder != "overrides");
+    Ok(pack)
+}
+
+/**
+ * Splits a curseforge loader id (`forge-47.2.0`, `fabric-0.15.7`) into a modrinth dependency key and version
+ */
+pub fn split_loader_id(id: &str) -> Result<(String, String)> {
+    let (name, version) = id
+        .split_once('-')
+        .ok_or(eyre!("Malformed modloader id {}", id))?;
+    let key = loader_key(name).ok_or(eyre!("Unknown modloader {}", name))?;
+    Ok((key.to_string(), version.to_string()))
+}
+
+/**
+ * Inverse of [split_loader_id]
+ */
+pub fn loader_id(key: &str, version: &str) -> String {
+    format!("{}-{}", loader_name(key), version)
+}
+
+/**
+ * Looks up the project of every curseforge entry, for [modlist_html]
+ */
+pub async fn lookup_projects(pack: &Pack, client: &ApiClient) -> Result<BTreeMap<u32, APIModData>> {
+    let mut projects = BTreeMap::new();
+    for cref in pack.entries.iter().filter_map(|e| e.curse.as_ref()) {
+        if let Entry::Vacant(slot) = projects.entry(cref.project_id) {
+            slot.insert(
+                curse::lookup_mod(client, cref.project_id)
+                    .await
+                    .wrap_err(format!(
+                        "Err while looking up curseforge project {}",
+                        cref.project_id
+                    ))?,
+            );
+        }
+    }
+    Ok(projects)
+}
+
+/**
+ * Lists the curseforge projects of a pack the way the curseforge app does in `modlist.html`
+ */
+pub fn modlist_html(pack: &Pack, projects: &BTreeMap<u32, APIModData>) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    let mut html = "<ul>\n".to_string();
+    for entry in &pack.entries {
+        let Some(cref) = &entry.curse else {
+            continue;
+        };
+        let line = match projects.get(&cref.project_id) {
+            Some(project) => {
+                let authors: Vec<&str> = project.authors.iter().map(|a| a.name.as_str()).collect();
+                let url = project.links.website_url.clone().unwrap_or(format!(
+                    "https://www.curseforge.com/minecraft/mc-mods/{}",
+                    project.slug
+                ));
+                format!(
+                    "<li><a href=\"{}\">{} (by {})</a></li>",
+                    escape(&url),
+                    escape(&project.name),
+                    escape(&authors.join(", "))
+                )
+            }
+            None => format!("<li>{}</li>", escape(&entry.label())),
+        };
+        html.push_str(&line);
+        html.push('\n');
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+/**
+ * Writes a [Pack] as a curseforge modpack zip, entries without a curseforge file are embedded into the overrides and
+ * `projects` (see [lookup_projects]) fills `modlist.html`
+ */
+pub fn write<W: Write + Seek>(
+    pack: &Pack,
+    out: &mut ZipWriter<W>,
+    compression: &Compression,
+    projects: &BTreeMap<u32, APIModData>,
+) -> Result<()> {
+    let folder = pack.overrides_folder.as_deref().unwrap_or("overrides");
+    let mut files = Vec::new();
+    for entry in &pack.entries {
+        if let Some(cref) = &entry.curse {
+            files.push(PackModDescription {
+                project_id: cref.project_id,
+                file_id: cref.file_id,
+                required: entry.required,
+            });
+            continue;
+        }
+        let contents = entry.contents.as_ref().ok_or(eyre!(
+            "{} is not on curseforge and was not downloaded",
+            entry.label()
+        ))?;
+        event!(Level::WARN, "Embedding {} into overrides", entry.label());
+        write_entry(
+            out,
+            &format!("{}/{}", folder, entry.path),
+            contents,
+            compression,
+        )?;
+    }
+    let meta = curse::PackMeta {
+        minecraft: PackMinecraftMetadata {
+            version: pack.minecraft.clone(),
+            mod_loaders: pack
+                .loaders
+                .iter()
+                .enumerate()
+                .map(|(index, (key, version))| PackModLoaderMetadata {
+                    id: loader_id(key, version),
+                    primary: index == 0,
+                })
+                .collect(),
+        },
+        manifest_type: "minecraftModpack".to_string(),
+        manifest_version: 1,
+        name: pack.name.clone(),
+        version: pack.version.clone(),
+        author: pack.author.clone(),
+        files,
+        overrides: folder.to_string(),
+    };
+    write_entry(
+        out,
+        "manifest.json",
+        &sorted_json(&meta, true)?,
+        compression,
+    )?;
+    let sidecar = Sidecar {
+        summary: pack.summary.clone(),
+        ..Default::default()
+    };
+    write_sidecar(out, &sidecar, compression)?;
+    write_entry(
+        out,
+        "modlist.html",
+        modlist_html(pack, projects).as_bytes(),
+        compression,
+    )?;
+    for (path, data) in merged_overrides(pack, Side::Client) {
+        write_entry(out, &format!("{}/{}", folder, path), data, compression)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::io::{Read, Seek, Write};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
+};
+use serde_json::Map;
+use zip::{ZipArchive, ZipWriter};
+
+use super::{
+    Compression, CurseRef, ModrinthRef, Pack, PackEntry, Sidecar, loader_key, loader_name,
+    read_entry, read_prefixed, sha1_hex, sorted_json, write_entry, write_sidecar,
+};
+use crate::install::{Side, merged_overrides};
+use crate::platforms::gdl::{InstanceMeta, InstanceMod, LoaderMeta, ProjectRef};
+
+/**
+ * Reads a zipped GDLauncher instance folder into a [Pack]
+ *
+ * Mods listed in `config.json` become entries carrying their contents, everything else becomes an override
+ */
+pub fn read<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Pack> {
+    let index = archive
+        .index_for_name("config.json")
+        .ok_or(eyre!("No config.json in archive"))?;
+    let meta = serde_json::from_slice::<InstanceMeta>(&read_entry(archive, index)?)
+        .wrap_err("Err while loading GDLauncher instance")?;
+    let mut pack = Pack {
+        minecraft: meta.loader.mc_version,
+        ..Default::default()
+    };
+    if let (Some(key), Some(version)) = (
+        loader_key(&meta.loader.loader_type),
+        meta.loader.loader_version,
+    ) {
+        pack.loaders.insert(key.to_string(), version);
+    }
+    let mut files = read_prefixed(archive, "")?;
+    files.remove("config.json");
+    for instance_mod in meta.mods {
+        let path = format!("mods/{}", instance_mod.file_name);
+        let mut entry = PackEntry {
+            display_name: Some(instance_mod.display_name).filter(|n| !n.is_empty()),
+            required: true,
+            contents: files.remove(&path),
+            ..Default::default()
+        };
+        match (instance_mod.project_id, instance_mod.file_id) {
+            (Some(ProjectRef::Curse(project_id)), Some(ProjectRef::Curse(file_id))) => {
+                entry.curse = Some(CurseRef {
+                    project_id,
+                    file_id,
+                });
+            }
+            (Some(ProjectRef::Modrinth(project_id)), Some(ProjectRef::Modrinth(version_id))) => {
+                entry.modrinth = Some(ModrinthRef {
+                    project_id,
+                    version_id,
+                });
+            }
+            _ => {}
+        }
+        if let Some(contents) = &entry.contents {
+            entry.sha1 = Some(sha1_hex(contents));
+            entry.file_size = Some(contents.len() as u64);
+        }
+        entry.path = path;
+        pack.entries.push(entry);
+    }
+    pack.overrides.common = files;
+    Ok(pack)
+}
+
+/**
+ * Writes a [Pack] as a zipped GDLauncher instance folder, every entry must carry its contents
+ */
+pub fn write<W: Write + Seek>(
+    pack: &Pack,
+    out: &mut ZipWriter<W>,
+    compression: &Compression,
+) -> Result<()> {
+    let mut mods = Vec::new();
+    for entry in &pack.entries {
+        let contents = entry
+            .contents
+            .as_ref()
+            .ok_or(eyre!("{} was not downloaded", entry.label()))?;
+        write_entry(out, &entry.path, contents, compression)?;
+        // config.json only lists mods, anything else is read back as a plain file
+        if !entry.path.starts_with("mods/") {
+            continue;
+        }
+        let (project_id, file_id, source) = match (&entry.curse, &entry.modrinth) {
+            (Some(c), _) => (
+                Some(ProjectRef::Curse(c.project_id)),
+                Some(ProjectRef::Curse(c.file_id)),
+                Some("curseforge".to_string()),
+            ),
+            (None, Some(m)) => (
+                Some(ProjectRef::Modrinth(m.project_id.clone())),
+                Some(ProjectRef::Modrinth(m.version_id.clone())),
+                Some("modrinth".to_string()),
+            ),
+            (None, None) => (None, None, None),
+        };
+        mods.push(InstanceMod {
+            file_name: entry.file_name().to_string(),
+            display_name: entry.label(),
+            project_id,
+            file_id,
+            source,
+            extra: Map::new(),
+        });
+    }
+    let loader = pack.loaders.iter().next();
+    let meta = InstanceMeta {
+        loader: LoaderMeta {
+            loader_type: loader
+                .map(|(key, _)| loader_name(key))
+                .unwrap_or("vanilla")
+                .to_string(),
+            mc_version: pack.minecraft.clone(),
+            loader_version: loader.map(|(_, version)| version.clone()),
+            project_id: None,
+            file_id: None,
+            source: None,
+            extra: Map::new(),
+        },
+        mods,
+        extra: Map::new(),
+    };
+    write_entry(out, "config.json", &sorted_json(&meta, true)?, compression)?;
+    // config.json has no pack metadata at all
+    let sidecar = Sidecar {
+        name: pack.name.clone(),
+        version: pack.version.clone(),
+        author: pack.author.clone(),
+        summary: pack.summary.clone(),
+    };
+    write_sidecar(out, &sidecar, compression)?;
+    for (path, data) in merged_overrides(pack, Side::Client) {
+        write_entry(out, path, data, compression)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,354 @@
+use std::io::{Read, Seek, Write};
+
+use color_eyre::{
+    Result,
+    eyre::{Context, eyre},
+};
+use tracing::{Level, event};
+use zip::{ZipArchive, ZipWriter};
+
+use super::{
+    ArchiveLimits, Compression, ModrinthRef, Pack, PackEntry, Sidecar, check_archive, is_safe_path,
+    read_entry, read_prefixed, sorted_json, write_entry, write_sidecar,
+};
+use crate::platforms::mr::{self, HashInfo, ModrinthEnvironmentRequirement};
+
+/**
+ * Reads a `.mrpack` into a [Pack]
+ */
+pub fn read<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Pack> {
+    let index = archive
+        .index_for_name("modrinth.index.json")
+        .ok_or(eyre!("No modrinth.index.json in archive"))?;
+    let meta = serde_json::from_slice::<mr::PackMeta>(&read_entry(archive, index)?)
+        .wrap_err("Err while loading modrinth index")?;
+    let mut pack = Pack {
+        name: meta.name,
+        version: meta.version_id,
+        summary: meta.summary,
+        ..Default::default()
+    };
+    for (key, value) in meta.dependencies {
+        if key == "minecraft" {
+            pack.minecraft = value;
+        } else {
+            pack.loaders.insert(key, value);
+        }
+    }
+    pack.entries = meta
+        .files
+        .into_iter()
+        .map(|file| PackEntry {
+            modrinth: file.downloads.iter().find_map(|url| parse_cdn_url(url)),
+            path: file.path,
+            sha1: Some(file.hashes.sha1),
+            sha512: Some(file.hashes.sha512),
+            file_size: Some(file.file_size as u64),
+            downloads: file.downloads,
+            required: file
+                .env
+                .as_ref()
+                .is_none_or(|env| !optional_everywhere(env)),
+            env: file.env,
+            ..Default::default()
+        })
+        .collect();
+    pack.overrides.common = read_prefixed(archive, "overrides/")?;
+    pack.overrides.client = read_prefixed(archive, "client-overrides/")?;
+    pack.overrides.server = read_prefixed(archive, "server-overrides/")?;
+    Ok(pack)
+}
+
+/**
+ * Hosts the mrpack spec allows `downloads` to point at
+ */
+pub const ALLOWED_DOWNLOAD_DOMAINS: [&str; 5] = [
+    "cdn.modrinth.com",
+    "edge.forgecdn.net",
+    "github.com",
+    "raw.githubusercontent.com",
+    "gitlab.com",
+];
+/**
+ * Keys the mrpack spec allows in `dependencies`
+ */
+pub const ALLOWED_DEPENDENCIES: [&str; 5] = [
+    "minecraft",
+    "forge",
+    "neoforge",
+    "fabric-loader",
+    "quilt-loader",
+];
+
+/**
+ * Whether `url` is https on one of the [ALLOWED_DOWNLOAD_DOMAINS]
+ */
+pub fn is_allowed_download(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|u| {
+        u.scheme() == "https"
+            && u.host_str()
+                .is_some_and(|h| ALLOWED_DOWNLOAD_DOMAINS.contains(&h))
+    })
+}
+/**
+ * Whether an index can link to `entry` instead of embedding it, it needs both hashes and an allowed download
+ */
+pub fn can_link(entry: &PackEntry) -> bool {
+    entry.sha1.is_some()
+        && entry.sha512.is_some()
+        && entry.downloads.iter().any(|url| is_allowed_download(url))
+}
+/**
+ * What [write] does with an entry whose only downloads are on hosts outside [ALLOWED_DOWNLOAD_DOMAINS]
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DownloadPolicy {
+    /// Embed the file into `overrides/`, it has to be downloaded first
+    #[default]
+    Embed,
+    /// Refuse to write the pack
+    Error,
+}
+impl DownloadPolicy {
+    /**
+     * Reads a `--disallowed-downloads` flag value
+     */
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "embed" => Ok(Self::Embed),
+            "error" => Ok(Self::Error),
+            other => Err(eyre!("Unknown download policy {}", other)),
+        }
+    }
+    /**
+     * Whether `entry` has to be downloaded before [write] under this policy
+     *
+     * An allowed download missing a hash is fetched under both, [Pack::fetch_contents] fills the hashes so it can be
+     * linked
+     */
+    pub fn needs_contents(&self, entry: &PackEntry) -> bool {
+        match self {
+            Self::Embed => !can_link(entry),
+            // anything else with a download it cannot link is refused by [write] rather than embedded
+            Self::Error => {
+                entry.downloads.is_empty()
+                    || (!can_link(entry) && entry.downloads.iter().any(|u| is_allowed_download(u)))
+            }
+        }
+    }
+}
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/**
+ * Checks a `modrinth.index.json` against the mrpack spec, returning every problem found
+ */
+pub fn validate(meta: &mr::PackMeta) -> Vec<String> {
+    let mut problems = Vec::new();
+    if meta.format_version != 1 {
+        problems.push(format!(
+            "formatVersion is {}, expected 1",
+            meta.format_version
+        ));
+    }
+    if meta.game != "minecraft" {
+        problems.push(format!("game is {}, expected minecraft", meta.game));
+    }
+    if meta
+        .dependencies
+        .get("minecraft")
+        .is_none_or(|version| version.is_empty())
+    {
+        problems.push("dependencies has no minecraft version".to_string());
+    }
+    for key in meta.dependencies.keys() {
+        if !ALLOWED_DEPENDENCIES.contains(&key.as_str()) {
+            problems.push(format!("dependencies has unknown key {}", key));
+        }
+    }
+    for file in &meta.files {
+        if !is_safe_path(&file.path) {
+            problems.push(format!("{} is not a safe relative path", file.path));
+        }
+        if !is_hex(&file.hashes.sha1, 40) {
+            problems.push(format!("{} has no valid sha1", file.path));
+        }
+        if !is_hex(&file.hashes.sha512, 128) {
+            problems.push(format!("{} has no valid sha512", file.path));
+        }
+        if file.downloads.is_empty() {
+            problems.push(format!("{} has no downloads", file.path));
+        }
+        for url in file.downloads.iter().filter(|u| !is_allowed_download(u)) {
+            problems.push(format!(
+                "{} downloads from {}, which is not an allowed domain",
+                file.path, url
+            ));
+        }
+        for side in file.env.iter().flat_map(|env| [&env.client, &env.server]) {
+            if !["required", "optional", "unsupported"].contains(&side.as_str()) {
+                problems.push(format!("{} has unknown env {}", file.path, side));
+            }
+        }
+    }
+    problems
+}
+
+/**
+ * Reads the `modrinth.index.json` of an `.mrpack` and [validate]s it
+ */
+pub fn validate_archive<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Vec<String>> {
+    check_archive(archive, &ArchiveLimits::default())?;
+    let index = archive
+        .index_for_name("modrinth.index.json")
+        .ok_or(eyre!("No modrinth.index.json in archive"))?;
+    let meta = serde_json::from_slice::<mr::PackMeta>(&read_entry(archive, index)?)
+        .wrap_err("Err while loading modrinth index")?;
+    Ok(validate(&meta))
+}
+
+/**
+ * Writes a [Pack] as a `.mrpack`, entries modrinth cannot reference are embedded into `overrides/`
+ *
+ * Only downloads on [ALLOWED_DOWNLOAD_DOMAINS] are linked, entries with none of those are handled as `downloads` says.
+ * The index is checked with [validate] before anything is written
+ */
+pub fn write<W: Write + Seek>(
+    pack: &Pack,
+    out: &mut ZipWriter<W>,
+    compression: &Compression,
+    downloads: DownloadPolicy,
+) -> Result<()> {
+    let mut files = Vec::new();
+    let mut embedded = Vec::new();
+    for entry in &pack.entries {
+        if downloads == DownloadPolicy::Error && !entry.downloads.is_empty() && !can_link(entry) {
+            return Err(eyre!(
+                "{} is only available from {}, which modrinth packs may not link to",
+                entry.label(),
+                entry.downloads.join(", ")
+            ));
+        }
+        if can_link(entry) {
+            files.push(mr::PackModDescription {
+                path: entry.path.clone(),
+                hashes: HashInfo {
+                    sha512: entry.sha512.clone().unwrap_or_default(),
+                    sha1: entry.sha1.clone().unwrap_or_default(),
+                },
+                env: Some(env_of(entry)),
+                downloads: entry
+                    .downloads
+                    .iter()
+                    .filter(|url| is_allowed_download(url))
+                    .cloned()
+                    .collect(),
+                file_size: entry.file_size.unwrap_or_default() as u32,
+            });
+            continue;
+        }
+        let contents = entry.contents.as_ref().ok_or(eyre!(
+            "{} cannot be linked from a modrinth pack and was not downloaded",
+            entry.label()
+        ))?;
+        embedded.push((entry, contents));
+    }
+    let mut dependencies = pack.loaders.clone();
+    dependencies.insert("minecraft".to_string(), pack.minecraft.clone());
+    let meta = mr::PackMeta {
+        game: "minecraft".to_string(),
+        format_version: 1,
+        version_id: pack.version.clone(),
+        name: pack.name.clone(),
+        summary: pack.summary.clone(),
+        files,
+        dependencies,
+    };
+    let problems = validate(&meta);
+    if !problems.is_empty() {
+        return Err(eyre!(
+            "Generated modrinth.index.json is invalid:\n{}",
+            problems.join("\n")
+        ));
+    }
+
+    for (entry, contents) in embedded {
+        event!(Level::WARN, "Embedding {} into overrides", entry.label());
+        write_entry(
+            out,
+            &format!("overrides/{}", entry.path),
+            contents,
+            compression,
+        )?;
+    }
+    write_entry(
+        out,
+        "modrinth.index.json",
+        &sorted_json(&meta, false)?,
+        compression,
+    )?;
+    let sidecar = Sidecar {
+        author: pack.author.clone(),
+        ..Default::default()
+    };
+    write_sidecar(out, &sidecar, compression)?;
+    for (prefix, files) in [
+        ("overrides", &pack.overrides.common),
+        ("client-overrides", &pack.overrides.client),
+        ("server-overrides", &pack.overrides.server),
+    ] {
+        for (path, data) in files {
+            write_entry(out, &format!("{}/{}", prefix, path), data, compression)?;
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Whether every side `env` runs on marks it `optional`, only then is the entry optional as a whole
+ */
+fn optional_everywhere(env: &ModrinthEnvironmentRequirement) -> bool {
+    let sides: Vec<&String> = [&env.client, &env.server]
+        .into_iter()
+        .filter(|side| *side != "unsupported")
+        .collect();
+    !sides.is_empty() && sides.iter().all(|side| *side == "optional")
+}
+/**
+ * Env written for `entry`, an env read from a modrinth index is kept as is, otherwise both sides are `required` or
+ * `optional` when the entry is not required (curseforge `required: false`)
+ */
+fn env_of(entry: &PackEntry) -> ModrinthEnvironmentRequirement {
+    if let Some(env) = &entry.env {
+        return env.clone();
+    }
+    let side = if entry.required {
+        "required"
+    } else {
+        "optional"
+    };
+    ModrinthEnvironmentRequirement {
+        client: side.to_string(),
+        server: side.to_string(),
+    }
+}
+
+/**
+ * Pulls project and version ids out of a `cdn.modrinth.com/data/{project}/versions/{version}/{file}` url
+ */
+pub fn parse_cdn_url(url: &str) -> Option<ModrinthRef> {
+    let rest = url
+        .strip_prefix("https://cdn.modrinth.com/data/")
+        .or(url.strip_prefix("http://cdn.modrinth.com/data/"))?;
+    let mut parts = rest.split('/');
+    let project_id = parts.next()?;
+    if parts.next()? != "versions" {
+        return None;
+    }
+    let version_id = parts.next()?;
+    Some(ModrinthRef {
+        project_id: project_id.to_string(),
+        version_id: version_id.to_string(),
+    })
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::platforms::mr::ModrinthFileInfo;
+
+//FILE CONTENT
+/**
+ * Instance file (`instance.json`) as stored in the root of an ATLauncher instance
+ *
+ * Only the fields multipack reads are modeled, everything else is kept in `extra` so the file survives a rewrite
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+pub struct InstanceMeta {
+    /// Minecraft version of the instance
+    pub id: String,
+    pub launcher: LauncherMeta,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * ATLauncher specific section of the [InstanceMeta]
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+#[serde(rename_all = "camelCase")]
+pub struct LauncherMeta {
+    pub name: String,
+    #[serde(default)]
+    pub pack: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader_version: Option<LoaderVersion>,
+    #[serde(default)]
+    pub mods: Vec<InstanceMod>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * Modloader installed into the instance
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+pub struct LoaderVersion {
+    pub version: String,
+    /// One of `Forge`, `NeoForge`, `Fabric` or `Quilt`
+    #[serde(rename = "type")]
+    pub loader_type: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * A mod, resource pack or shader tracked by the instance, generally in a [Vec] in the [LauncherMeta]
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMod {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub optional: bool,
+    pub file: String,
+    /// Install target, `mods`, `resourcepack`, `shaderpack`, ...
+    #[serde(rename = "type")]
+    pub mod_type: String,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub user_added: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curse_forge_project_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curse_forge_file_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modrinth_project: Option<ModrinthProjectRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modrinth_version: Option<ModrinthVersionRef>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * Cached modrinth project as stored on an [InstanceMod]
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+pub struct ModrinthProjectRef {
+    pub id: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * Cached modrinth version as stored on an [InstanceMod]
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+pub struct ModrinthVersionRef {
+    pub id: String,
+    pub project_id: String,
+    #[serde(default)]
+    pub files: Vec<ModrinthFileInfo>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
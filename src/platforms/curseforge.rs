@@ -1,10 +1,11 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use tracing::{Level, event};
 
 //FILE CONTENT
 /**
  * Pack metadata file as contained in the modpack zip
  */
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(unused)]
 #[serde(rename_all = "camelCase")]
 pub struct PackMeta {
@@ -20,7 +21,7 @@ pub struct PackMeta {
 /**
  * Metadata for the Minecraft version requirement of a modpack
  */
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(unused)]
 #[serde(rename_all = "camelCase")]
 pub struct PackMinecraftMetadata {
@@ -30,7 +31,7 @@ pub struct PackMinecraftMetadata {
 /**
  * Metadata for the Minecraft modloaders (quilt, neo/forge, etc) required by a modpack
  */
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 #[allow(unused)]
 pub struct PackModLoaderMetadata {
     pub id: String,
@@ -39,7 +40,7 @@ pub struct PackModLoaderMetadata {
 /**
  * Metadata for a mod, generally in a [Vec] in the [PackMeta]
  */
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[allow(unused)]
 pub struct PackModDescription {
     #[serde(rename = "projectID")]
@@ -65,6 +66,39 @@ pub struct ModLookupResponse {
 pub struct FileLookupResponse {
     pub data: APIFile,
 }
+/**
+ * Response to curse api @ POST /v1/fingerprints
+ */
+#[derive(Deserialize)]
+#[allow(unused)]
+pub struct FingerprintResponse {
+    pub data: FingerprintMatches,
+}
+/**
+ * Files the curse api knows by fingerprint, see [FingerprintResponse]
+ */
+#[derive(Deserialize)]
+#[allow(unused)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintMatches {
+    pub is_cache_built: bool,
+    pub exact_matches: Vec<FingerprintMatch>,
+    pub exact_fingerprints: Vec<i64>,
+    pub installed_fingerprints: Vec<i64>,
+    pub unmatched_fingerprints: Vec<i64>,
+}
+/**
+ * A file found by its fingerprint and the project it belongs to
+ */
+#[derive(Deserialize)]
+#[allow(unused)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintMatch {
+    /// Project id
+    pub id: u32,
+    pub file: APIFile,
+    pub latest_files: Vec<APIFile>,
+}
 /**
  * Data about a mod as retrieved by the curse api
  */
@@ -85,19 +119,20 @@ pub struct APIModData {
     pub categories: Vec<ModCategory>,
     pub class_id: Option<u32>,
     pub authors: Vec<ModAuthor>,
-    pub logo: AssetMeta,
-    pub screenshots: AssetMeta,
+    pub logo: Option<AssetMeta>,
+    pub screenshots: Vec<AssetMeta>,
     pub main_file_id: u32,
     pub latest_files: Vec<APIFile>,
+    #[serde(rename = "latestFilesIndexes")]
     pub latest_file_indexes: Vec<FileIndex>,
     pub latest_early_access_files_indexes: Vec<FileIndex>,
     pub date_created: String,
     pub date_modified: String,
     pub date_released: String,
     pub allow_mod_distribution: Option<bool>,
-    pub game_popularity_link: u32,
+    pub game_popularity_rank: u32,
     pub is_available: bool,
-    pub thumbs_up_count: bool,
+    pub thumbs_up_count: u32,
     pub rating: Option<f64>,
 }
 /**
@@ -107,15 +142,15 @@ pub struct APIModData {
 #[allow(unused)]
 #[serde(rename_all = "camelCase")]
 pub struct ModLinks {
-    pub website_url: String,
-    pub wiki_url: String,
-    pub issues_url: String,
-    pub source_url: String,
+    pub website_url: Option<String>,
+    pub wiki_url: Option<String>,
+    pub issues_url: Option<String>,
+    pub source_url: Option<String>,
 }
 /**
  * The status of a mod as listed in the curseforge api
  */
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum ModStatus {
     New,
     ChangesRequired,
@@ -127,12 +162,15 @@ pub enum ModStatus {
     Abandoned,
     Deleted,
     UnderReview,
+    /// Value added to the api after this was written
+    Unknown(u32),
 }
 /**
  * The category that this [APIModData] is categorized into
  */
 #[derive(Deserialize, Clone)]
 #[allow(unused)]
+#[serde(rename_all = "camelCase")]
 pub struct ModCategory {
     pub id: u32,
     pub game_id: u32,
@@ -161,6 +199,7 @@ pub struct ModAuthor {
  */
 #[derive(Deserialize, Clone)]
 #[allow(unused)]
+#[serde(rename_all = "camelCase")]
 pub struct AssetMeta {
     pub id: u32,
     pub mod_id: u32,
@@ -205,16 +244,18 @@ pub struct APIFile {
 /**
  * The release state (Alpha/beta or Release)
  */
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
 pub enum ReleaseType {
     Release,
     Beta,
     Alpha,
+    /// Value added to the api after this was written
+    Unknown(u32),
 }
 /**
  * Status of this file
  */
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum FileStatus {
     Processing,
     ChangesRequired,
@@ -239,6 +280,8 @@ pub enum FileStatus {
     PendingRelease,
     ReadyForCooking,
     PostProcessing,
+    /// Value added to the api after this was written
+    Unknown(u32),
 }
 /**
  * File hash w/ algorithm specifier
@@ -256,6 +299,8 @@ pub struct FileHashData {
 pub enum HashAlgo {
     Sha1,
     Md5,
+    /// Value added to the api after this was written
+    Unknown(u32),
 }
 /**
  * Game version specs but easier to sort
@@ -289,8 +334,10 @@ pub enum RelationType {
     OptionalDependency,
     RequiredDependency,
     Tool,
-    Incomplete,
+    Incompatible,
     Include,
+    /// Value added to the api after this was written
+    Unknown(u32),
 }
 /**
  * Curse module related to this file
@@ -313,12 +360,12 @@ pub struct FileIndex {
     pub filename: String,
     pub release_type: ReleaseType,
     pub game_version_type_id: Option<u32>,
-    pub mod_loader: CurseModLoaderType,
+    pub mod_loader: Option<CurseModLoaderType>,
 }
 /**
  * Modloader required for this file
  */
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum CurseModLoaderType {
     Any,
     Forge,
@@ -327,181 +374,118 @@ pub enum CurseModLoaderType {
     Fabric,
     Quilt,
     NeoForge,
+    /// Value added to the api after this was written
+    Unknown(u32),
 }
 
-//enum deserializer impls
+//enum (de)serializer impls
 // * * * * * * * //
-impl<'de> Deserialize<'de> for ModStatus {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        match u32::deserialize(deserializer) {
-            Ok(i) => {
-                if i <= 10 && i >= 1 {
-                    Ok([
-                        Self::New,
-                        Self::ChangesRequired,
-                        Self::UnderSoftReview,
-                        Self::Approved,
-                        Self::Rejected,
-                        Self::ChangesMade,
-                        Self::Inactive,
-                        Self::Abandoned,
-                        Self::Deleted,
-                        Self::UnderReview,
-                    ][i as usize - 1]
-                        .clone())
-                } else {
-                    Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Unsigned(i as u64),
-                        &"a number [1,10]",
-                    ))
-                }
-            }
-            Err(e) => Err(e),
-        }
-    }
-}
-impl<'de> Deserialize<'de> for CurseModLoaderType {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        match u32::deserialize(deserializer) {
-            Ok(i) => {
-                if i <= 6 {
-                    Ok([
-                        Self::Any,
-                        Self::Forge,
-                        Self::Cauldron,
-                        Self::LiteLoader,
-                        Self::Fabric,
-                        Self::Quilt,
-                        Self::NeoForge,
-                    ][i as usize]
-                        .clone())
-                } else {
-                    Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Unsigned(i as u64),
-                        &"a number [0,6]",
-                    ))
-                }
-            }
-            Err(e) => Err(e),
-        }
-    }
-}
-impl<'de> Deserialize<'de> for RelationType {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        match u32::deserialize(deserializer) {
-            Ok(i) => {
-                if i <= 6 && i >= 1 {
-                    Ok([
-                        Self::EmbeddedLibrary,
-                        Self::OptionalDependency,
-                        Self::RequiredDependency,
-                        Self::Tool,
-                        Self::Incomplete,
-                        Self::Include,
-                    ][i as usize - 1]
-                        .clone())
-                } else {
-                    Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Unsigned(i as u64),
-                        &"a number [1,6]",
-                    ))
-                }
-            }
-            Err(e) => Err(e),
-        }
-    }
-}
-impl<'de> Deserialize<'de> for HashAlgo {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        match u32::deserialize(deserializer) {
-            Ok(i) => {
-                if i >= 1 && i <= 2 {
-                    Ok([Self::Sha1, Self::Md5][i as usize - 1].clone())
-                } else {
-                    Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Unsigned(i as u64),
-                        &"a number [1,2]",
-                    ))
+/**
+ * Reads and writes an api enum as the number curseforge uses for it, numbers without a variant become `Unknown` with
+ * a warning so responses keep parsing when curseforge adds values
+ */
+macro_rules! numeric_enum {
+    ($name:ident { $($value:literal => $variant:ident),* $(,)? }) => {
+        impl $name {
+            /**
+             * Number curseforge uses for this value
+             */
+            pub fn id(&self) -> u32 {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Unknown(i) => *i,
                 }
             }
-            Err(e) => Err(e),
         }
-    }
-}
-impl<'de> Deserialize<'de> for FileStatus {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        match u32::deserialize(deserializer) {
-            Ok(i) => {
-                if i <= 23 && i >= 1 {
-                    Ok([
-                        Self::Processing,
-                        Self::ChangesRequired,
-                        Self::UnderReview,
-                        Self::Approved,
-                        Self::Rejected,
-                        Self::MalwareDetected,
-                        Self::Deleted,
-                        Self::Archived,
-                        Self::Testing,
-                        Self::Released,
-                        Self::ReadyForReview,
-                        Self::Deprecated,
-                        Self::Baking,
-                        Self::AwaitingPublishing,
-                        Self::FailedPublishing,
-                        Self::Cooking,
-                        Self::Cooked,
-                        Self::UnderManualReview,
-                        Self::ScanningForMalware,
-                        Self::ProcessingFile,
-                        Self::PendingRelease,
-                        Self::ReadyForCooking,
-                        Self::PostProcessing,
-                    ][i as usize - 1]
-                        .clone())
-                } else {
-                    Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Unsigned(i as u64),
-                        &"a number [1,23]",
-                    ))
-                }
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                Ok(match u32::deserialize(deserializer)? {
+                    $($value => Self::$variant,)*
+                    i => {
+                        event!(
+                            Level::WARN,
+                            "Unknown curseforge {} {}, keeping it as is",
+                            stringify!($name),
+                            i
+                        );
+                        Self::Unknown(i)
+                    }
+                })
             }
-            Err(e) => Err(e),
         }
-    }
-}
-impl<'de> Deserialize<'de> for ReleaseType {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        match u32::deserialize(deserializer) {
-            Ok(i) => {
-                if i <= 3 && i >= 1 {
-                    Ok([Self::Release, Self::Beta, Self::Alpha][i as usize - 1].clone())
-                } else {
-                    Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Unsigned(i as u64),
-                        &"a number [1,3]",
-                    ))
-                }
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_u32(self.id())
             }
-            Err(e) => Err(e),
         }
-    }
+    };
 }
+numeric_enum!(ModStatus {
+    1 => New,
+    2 => ChangesRequired,
+    3 => UnderSoftReview,
+    4 => Approved,
+    5 => Rejected,
+    6 => ChangesMade,
+    7 => Inactive,
+    8 => Abandoned,
+    9 => Deleted,
+    10 => UnderReview,
+});
+numeric_enum!(CurseModLoaderType {
+    0 => Any,
+    1 => Forge,
+    2 => Cauldron,
+    3 => LiteLoader,
+    4 => Fabric,
+    5 => Quilt,
+    6 => NeoForge,
+});
+numeric_enum!(RelationType {
+    1 => EmbeddedLibrary,
+    2 => OptionalDependency,
+    3 => RequiredDependency,
+    4 => Tool,
+    5 => Incompatible,
+    6 => Include,
+});
+numeric_enum!(HashAlgo {
+    1 => Sha1,
+    2 => Md5,
+});
+numeric_enum!(FileStatus {
+    1 => Processing,
+    2 => ChangesRequired,
+    3 => UnderReview,
+    4 => Approved,
+    5 => Rejected,
+    6 => MalwareDetected,
+    7 => Deleted,
+    8 => Archived,
+    9 => Testing,
+    10 => Released,
+    11 => ReadyForReview,
+    12 => Deprecated,
+    13 => Baking,
+    14 => AwaitingPublishing,
+    15 => FailedPublishing,
+    16 => Cooking,
+    17 => Cooked,
+    18 => UnderManualReview,
+    19 => ScanningForMalware,
+    20 => ProcessingFile,
+    21 => PendingRelease,
+    22 => ReadyForCooking,
+    23 => PostProcessing,
+});
+numeric_enum!(ReleaseType {
+    1 => Release,
+    2 => Beta,
+    3 => Alpha,
+});
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+//FILE CONTENT
+/**
+ * Instance file (`config.json`) as stored in the root of a GDLauncher instance
+ *
+ * GDLauncher Carbon exports to the curseforge and modrinth formats, so only the legacy instance layout needs its own type
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+pub struct InstanceMeta {
+    pub loader: LoaderMeta,
+    #[serde(default)]
+    pub mods: Vec<InstanceMod>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * Minecraft version and modloader of the instance
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+#[serde(rename_all = "camelCase")]
+pub struct LoaderMeta {
+    /// One of `vanilla`, `forge`, `neoforge`, `fabric` or `quilt`
+    pub loader_type: String,
+    pub mc_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader_version: Option<String>,
+    /// Modpack this instance was installed from
+    #[serde(rename = "projectID", default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<ProjectRef>,
+    #[serde(rename = "fileID", default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<ProjectRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * A mod tracked by the instance, generally in a [Vec] in the [InstanceMeta]
+ */
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[allow(unused)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMod {
+    pub file_name: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(rename = "projectID", default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<ProjectRef>,
+    #[serde(rename = "fileID", default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<ProjectRef>,
+    /// `curseforge` or `modrinth`, missing on mods installed before modrinth support
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+/**
+ * Project or file id, numeric for curseforge and a string for modrinth
+ */
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum ProjectRef {
+    Curse(u32),
+    Modrinth(String),
+}
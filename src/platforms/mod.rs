@@ -1,11 +1,77 @@
+mod atlauncher;
 mod curseforge;
-mod modrinth;
+mod gdlauncher;
 pub mod mod_data;
+mod modrinth;
+
 pub mod curse {
     pub use super::curseforge::*;
     pub use super::mod_data::curse_mod_data::*;
 }
 pub mod mr {
-    pub use super::modrinth::*;
     pub use super::mod_data::modrinth_mod_data::*;
-}
\ No newline at end of file
+    pub use super::modrinth::*;
+}
+pub mod atl {
+    pub use super::atlauncher::*;
+}
+pub mod gdl {
+    pub use super::gdlauncher::*;
+}
+
+/**
+ * Base urls of the curseforge and modrinth apis, without a trailing `/`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiUrls {
+    pub curse: String,
+    pub modrinth: String,
+}
+impl Default for ApiUrls {
+    fn default() -> Self {
+        Self {
+            curse: "https://api.curseforge.com".to_string(),
+            modrinth: "https://api.modrinth.com".to_string(),
+        }
+    }
+}
+impl ApiUrls {
+    /**
+     * `CURSE_API_URL` and `MODRINTH_API_URL`, falling back to the public apis
+     */
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str, default: String| std::env::var(name).unwrap_or(default);
+        Self {
+            curse: var("CURSE_API_URL", default.curse),
+            modrinth: var("MODRINTH_API_URL", default.modrinth),
+        }
+        .trimmed()
+    }
+    fn trimmed(self) -> Self {
+        Self {
+            curse: self.curse.trim_end_matches('/').to_string(),
+            modrinth: self.modrinth.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+/**
+ * Http client for every api call and download, with the api base urls it talks to
+ *
+ * Cloning is cheap, [reqwest::Client] is reference counted
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ApiClient {
+    pub http: reqwest::Client,
+    pub urls: ApiUrls,
+}
+impl ApiClient {
+    /**
+     * Points this client at `urls` instead of the ones it was built with
+     */
+    pub fn with_urls(mut self, urls: ApiUrls) -> Self {
+        self.urls = urls.trimmed();
+        self
+    }
+}
@@ -3,10 +3,8 @@ pub mod modrinth_mod_data;
 
 use std::{error::Error, fmt::Display};
 
-use crate::platforms::{curse::CurseDependency, mr::ModrinthDependency};
-
 #[derive(Debug)]
-pub struct FetchError(String);
+pub struct FetchError(pub String);
 impl Display for FetchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -21,6 +19,18 @@ pub struct ModInfo<ConfType, ResolvableType, DepType> {
     pub deps: Option<Vec<DepType>>,
     pub sha1: Option<String>,
     pub file_name: Option<String>,
-    pub(in crate::platforms::mod_data) client: reqwest::Client, //reqwest::Client already uses Arc internally for clones (i am brain damaged)
+    pub(in crate::platforms::mod_data) client: crate::platforms::ApiClient, //reqwest::Client already uses Arc internally for clones (i am brain damaged)
     pub(super) resolved: bool,
 }
+impl<ConfType, ResolvableType, DepType> ModInfo<ConfType, ResolvableType, DepType> {
+    /**
+     * Remote info, [None] until `resolve_remotes` succeeded
+     */
+    pub fn resolved_info(&self) -> Option<&ResolvableType> {
+        if self.resolved {
+            self.resolved_info.as_ref()
+        } else {
+            None
+        }
+    }
+}
@@ -1,8 +1,10 @@
-use crate::ModInfo;
+use crate::platforms::ApiClient;
 use crate::platforms::curse::{
-    APIFile, HashAlgo, PackModDescription, RelationType, SortableGameVersion,
+    APIFile, APIModData, FingerprintMatch, FingerprintResponse, HashAlgo, ModLookupResponse,
+    PackModDescription, RelationType,
 };
-use color_eyre::{Result, eyre};
+use crate::platforms::mod_data::ModInfo;
+use color_eyre::Result;
 
 impl From<PackModDescription> for ModInfo<PackModDescription, APIFile, CurseDependency> {
     fn from(value: PackModDescription) -> Self {
@@ -10,7 +12,7 @@ impl From<PackModDescription> for ModInfo<PackModDescription, APIFile, CurseDepe
             config: value,
             resolved_info: None,
             deps: None,
-            client: reqwest::Client::default(),
+            client: ApiClient::default(),
             file_name: None,
             resolved: false,
             sha1: None,
@@ -21,9 +23,10 @@ impl ModInfo<PackModDescription, APIFile, CurseDependency> {
     pub async fn resolve_remotes(&mut self) -> Result<()> {
         let resp = self
             .client
+            .http
             .get(format!(
-                "https://api.curseforge.com/v1/mods/{}/files/{}",
-                self.config.project_id, self.config.file_id
+                "{}/v1/mods/{}/files/{}",
+                self.client.urls.curse, self.config.project_id, self.config.file_id
             ))
             .send()
             .await?;
@@ -43,18 +46,18 @@ impl ModInfo<PackModDescription, APIFile, CurseDependency> {
                 .hashes
                 .iter()
                 .find(|h| h.algo == HashAlgo::Sha1)
-                .take()
                 .ok_or("No SHA1 hash in api response")
                 .map_err(|e| super::FetchError(e.to_string()))
                 .map(|f| f.value.clone())?;
             self.sha1 = Some(hash);
+            self.file_name = Some(data.file_name.clone());
             self.deps = Some(
                 data.dependencies
                     .iter()
-                    .filter(|dep| dep.relation_type == RelationType::RequiredDependency)
-                    .map(|dep| CurseDependency(dep.mod_id))
+                    .map(|dep| CurseDependency(dep.mod_id, dep.relation_type.clone()))
                     .collect(),
             );
+            self.resolved = true;
         } else {
             return Err(super::FetchError("Bad response".to_string()).into());
         }
@@ -63,30 +66,62 @@ impl ModInfo<PackModDescription, APIFile, CurseDependency> {
     /**
      * Overwrites [self] with a reference to a global client
      */
-    pub fn with_shared_client(mut self, client: reqwest::Client) -> Self {
+    pub fn with_shared_client(mut self, client: ApiClient) -> Self {
         self.client = client.clone();
-        return self;
+        self
     }
-    pub async fn resolve(&mut self) -> Result<()> {
-        let d_arr = match &self.deps {
-            None => {
-                tracing::event!(tracing::Level::DEBUG, "No dependencies to fetch");
-                &vec![]
-            }
-            Some(d) => d,
-        };
-        let mut dep_hashes: Vec<String> = vec![];
-        let (svn, curse_ver_id) =
-            (|ver: &SortableGameVersion| -> (String, u32) {
-                (
-                    ver.game_version.clone(),
-                    ver.game_version_type_id.unwrap().clone(),
-                )
-            })(&(self.resolved_info.as_ref().unwrap().sortable_game_versions[0]));
-        for dep in d_arr {}
-
-        Ok(())
+}
+/**
+ * Fetches a mod (project) from the curse api
+ */
+pub async fn lookup_mod(client: &ApiClient, project_id: u32) -> Result<APIModData> {
+    let resp = client
+        .http
+        .get(format!("{}/v1/mods/{}", client.urls.curse, project_id))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(super::FetchError(format!(
+            "Could not resolve mod {}: http err {}",
+            project_id,
+            resp.status()
+        ))
+        .into());
+    }
+    Ok(serde_json::from_str::<ModLookupResponse>(resp.text().await?.as_str())?.data)
+}
+/**
+ * Looks up files by their curse fingerprint, see [crate::packs::curse_fingerprint], fingerprints curse does not know
+ * are left out
+ */
+pub async fn lookup_fingerprints(
+    client: &ApiClient,
+    fingerprints: &[u32],
+) -> Result<Vec<FingerprintMatch>> {
+    let resp = client
+        .http
+        .post(format!("{}/v1/fingerprints", client.urls.curse))
+        .header("content-type", "application/json")
+        .body(serde_json::to_vec(
+            &serde_json::json!({ "fingerprints": fingerprints }),
+        )?)
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(super::FetchError(format!(
+            "Could not look up fingerprints: http err {}",
+            resp.status()
+        ))
+        .into());
     }
+    Ok(
+        serde_json::from_str::<FingerprintResponse>(resp.text().await?.as_str())?
+            .data
+            .exact_matches,
+    )
 }
-#[derive(Debug)]
-pub struct CurseDependency(u32);
+/**
+ * Mod id a file relates to and how
+ */
+#[derive(Debug, Clone)]
+pub struct CurseDependency(pub u32, pub RelationType);
@@ -1,18 +1,131 @@
 use super::ModInfo;
-use crate::platforms::mr::{PackModDescription, VersionFileResponse};
-impl From<PackModDescription> for ModInfo<PackModDescription, VersionFileResponse, ModrinthDependency> {
+use crate::platforms::ApiClient;
+use crate::platforms::mr::{PackModDescription, ProjectResponse, VersionFileResponse};
+use color_eyre::Result;
+
+impl From<PackModDescription>
+    for ModInfo<PackModDescription, VersionFileResponse, ModrinthDependency>
+{
     fn from(value: PackModDescription) -> Self {
         Self {
             config: value,
             resolved_info: None,
             deps: None,
-            client: reqwest::Client::default(),
+            client: ApiClient::default(),
             file_name: None,
             resolved: false,
             sha1: None,
         }
     }
 }
+/**
+ * Looks up the version owning a file by its sha1, [None] if modrinth does not know the file
+ */
+pub async fn lookup_hash(client: &ApiClient, sha1: &str) -> Result<Option<VersionFileResponse>> {
+    let resp = client
+        .http
+        .get(format!(
+            "{}/v2/version_file/{}?algorithm=sha1",
+            client.urls.modrinth, sha1
+        ))
+        .send()
+        .await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(super::FetchError(format!(
+            "Could not look up hash: http err {}",
+            resp.status()
+        ))
+        .into());
+    }
+    Ok(Some(serde_json::from_str::<VersionFileResponse>(
+        resp.text().await?.as_str(),
+    )?))
+}
+/**
+ * Fetches a version by its id
+ */
+pub async fn lookup_version(client: &ApiClient, version_id: &str) -> Result<VersionFileResponse> {
+    let resp = client
+        .http
+        .get(format!(
+            "{}/v2/version/{}",
+            client.urls.modrinth, version_id
+        ))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(super::FetchError(format!(
+            "Could not resolve version {}: http err {}",
+            version_id,
+            resp.status()
+        ))
+        .into());
+    }
+    Ok(serde_json::from_str::<VersionFileResponse>(
+        resp.text().await?.as_str(),
+    )?)
+}
+/**
+ * Fetches a project by its id or slug
+ */
+pub async fn lookup_project(client: &ApiClient, project_id: &str) -> Result<ProjectResponse> {
+    let resp = client
+        .http
+        .get(format!(
+            "{}/v2/project/{}",
+            client.urls.modrinth, project_id
+        ))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(super::FetchError(format!(
+            "Could not resolve project {}: http err {}",
+            project_id,
+            resp.status()
+        ))
+        .into());
+    }
+    Ok(serde_json::from_str::<ProjectResponse>(
+        resp.text().await?.as_str(),
+    )?)
+}
+/**
+ * Lists the versions of a project matching `loaders` and `game_versions`, newest first
+ */
+pub async fn project_versions(
+    client: &ApiClient,
+    project_id: &str,
+    loaders: &[&str],
+    game_versions: &[&str],
+) -> Result<Vec<VersionFileResponse>> {
+    let resp = client
+        .http
+        .get(format!(
+            "{}/v2/project/{}/version",
+            client.urls.modrinth, project_id
+        ))
+        .query(&[
+            ("loaders", serde_json::to_string(loaders)?),
+            ("game_versions", serde_json::to_string(game_versions)?),
+        ])
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(super::FetchError(format!(
+            "Could not list versions of {}: http err {}",
+            project_id,
+            resp.status()
+        ))
+        .into());
+    }
+    Ok(serde_json::from_str::<Vec<VersionFileResponse>>(
+        resp.text().await?.as_str(),
+    )?)
+}
 
-#[derive(Debug)]
-pub struct ModrinthDependency(String);
+#[derive(Debug, Clone)]
+#[allow(unused)]
+pub struct ModrinthDependency(pub String);
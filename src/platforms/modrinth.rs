@@ -14,6 +14,8 @@ pub struct PackMeta {
     #[serde(rename = "versionId")]
     pub version_id: String,
     pub name: String,
+    /// Optional in the spec
+    #[serde(default)]
     pub summary: String,
     pub files: Vec<PackModDescription>,
     pub dependencies: BTreeMap<String, String>,
@@ -26,7 +28,9 @@ pub struct PackMeta {
 pub struct PackModDescription {
     pub path: String,
     pub hashes: HashInfo,
-    pub env: ModrinthEnvironmentRequirement,
+    /// Optional in the spec, a missing env means required on both sides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<ModrinthEnvironmentRequirement>,
     pub downloads: Vec<String>,
     #[serde(rename = "fileSize")]
     pub file_size: u32,
@@ -34,16 +38,16 @@ pub struct PackModDescription {
 /**
  * Hashes for a [PackModDescription]
  */
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[allow(unused)]
 pub struct HashInfo {
-    pub sha512:String,
-    pub sha1:String
+    pub sha512: String,
+    pub sha1: String,
 }
 /**
  * Requirement state on client and server
  */
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[allow(unused)]
 pub struct ModrinthEnvironmentRequirement {
     pub client: String,
@@ -54,48 +58,61 @@ pub struct ModrinthEnvironmentRequirement {
 /**
  * Response to modrinth api @ GET /v2/version_file/{SHA1_HASH}
  */
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 #[allow(unused)]
 pub struct VersionFileResponse {
-    pub name:String,
-    pub version_number:String,
-    pub changelog:Option<String>,
-    pub dependencies:Vec<DependencyInfo>,
-    pub game_versions:Vec<String>,
-    pub version_type:String,
-    pub loaders:Vec<String>,
-    pub featured:bool,
-    pub status:String,
-    pub requested_status:Option<String>,
-    pub id:String,
-    pub project_id:String,
-    pub author_id:String,
-    pub date_published:String,
-    pub downloads:u32,
-    pub changelog_url:Option<String>,
-    pub files:Vec<ModrinthFileInfo>
+    pub name: String,
+    pub version_number: String,
+    pub changelog: Option<String>,
+    pub dependencies: Vec<DependencyInfo>,
+    pub game_versions: Vec<String>,
+    pub version_type: String,
+    pub loaders: Vec<String>,
+    pub featured: bool,
+    pub status: String,
+    pub requested_status: Option<String>,
+    pub id: String,
+    pub project_id: String,
+    pub author_id: String,
+    pub date_published: String,
+    pub downloads: u32,
+    pub changelog_url: Option<String>,
+    pub files: Vec<ModrinthFileInfo>,
 }
 /**
  * Project information about a dep for this mod
  */
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Debug)]
 #[allow(unused)]
 pub struct DependencyInfo {
-    pub version_id:Option<String>,
-    pub project_id:Option<String>,
-    pub file_name:Option<String>,
-    pub dependency_type:String
+    pub version_id: Option<String>,
+    pub project_id: Option<String>,
+    pub file_name: Option<String>,
+    pub dependency_type: String,
 }
 /**
  * Info about this mod version
  */
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[allow(unused)]
 pub struct ModrinthFileInfo {
-    pub hashes:HashInfo,
-    pub url:String,
-    pub filename:String,
-    pub primary:bool,
-    pub size:u32,
-    pub file_type:Option<String>
+    pub hashes: HashInfo,
+    pub url: String,
+    pub filename: String,
+    pub primary: bool,
+    pub size: u32,
+    pub file_type: Option<String>,
+}
+/**
+ * Response to modrinth api @ GET /v2/project/{ID}
+ */
+#[derive(Deserialize, Clone, Debug)]
+#[allow(unused)]
+pub struct ProjectResponse {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    /// `mod`, `modpack`, `resourcepack`, `shader` or `datapack`
+    pub project_type: String,
+    pub loaders: Vec<String>,
 }
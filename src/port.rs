@@ -0,0 +1,162 @@
+use std::fmt::Display;
+
+use color_eyre::{Result, eyre::eyre};
+use serde::Serialize;
+use tracing::{Level, event};
+
+use crate::{
+    diff::EntrySummary,
+    outdated::{self, Update, curse_candidate, make_update, modrinth_candidate},
+    packs::{Pack, loader_key},
+    platforms::{ApiClient, curse::ReleaseType},
+};
+
+/**
+ * Game version and loader a pack is moved to
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct PortTarget {
+    pub minecraft: String,
+    /// Modrinth dependency key of the loader
+    pub loader: String,
+    pub loader_version: String,
+}
+/**
+ * Result of looking up every entry for a [PortTarget]
+ */
+#[derive(Serialize, Debug)]
+pub struct PortReport {
+    pub target: PortTarget,
+    pub ported: Vec<Update>,
+    pub missing: Vec<EntrySummary>,
+}
+
+impl PortTarget {
+    /**
+     * Builds the target from the `--mc`, `--loader` and `--loader-version` flags, keeping the pack's loader when none
+     * is given
+     */
+    pub fn new(
+        pack: &Pack,
+        minecraft: &str,
+        loader: Option<&str>,
+        loader_version: Option<&str>,
+    ) -> Result<Self> {
+        let (current_key, current_version) = pack
+            .loaders
+            .iter()
+            .next()
+            .ok_or(eyre!("Pack has no modloader"))?;
+        let key = match loader {
+            Some(name) => loader_key(name)
+                .ok_or(eyre!("Unknown modloader {}", name))?
+                .to_string(),
+            None => current_key.clone(),
+        };
+        let version = match loader_version {
+            Some(v) => v.to_string(),
+            None if key == *current_key => {
+                if key == "forge" || key == "neoforge" {
+                    event!(
+                        Level::WARN,
+                        "Keeping {} {}, {} versions are tied to a minecraft version, pass --loader-version",
+                        key,
+                        current_version,
+                        key
+                    );
+                }
+                current_version.clone()
+            }
+            None => {
+                return Err(eyre!("Switching to {} needs --loader-version", key));
+            }
+        };
+        Ok(Self {
+            minecraft: minecraft.to_string(),
+            loader: key,
+            loader_version: version,
+        })
+    }
+}
+
+/**
+ * Looks every entry up on modrinth and curseforge for a file matching `target`, modrinth is tried first when the
+ * entry is known there
+ */
+pub async fn port(
+    pack: &Pack,
+    client: &ApiClient,
+    target: PortTarget,
+    channel: ReleaseType,
+) -> Result<PortReport> {
+    let mut report = PortReport {
+        target,
+        ported: Vec::new(),
+        missing: Vec::new(),
+    };
+    let (minecraft, loader) = (
+        report.target.minecraft.as_str(),
+        Some(report.target.loader.as_str()),
+    );
+    for (index, entry) in pack.entries.iter().enumerate() {
+        let mut found = None;
+        if let Some(mref) = &entry.modrinth {
+            found =
+                modrinth_candidate(client, &mref.project_id, minecraft, loader, channel).await?;
+        }
+        if found.is_none()
+            && let Some(cref) = &entry.curse
+        {
+            found = curse_candidate(client, cref.project_id, minecraft, loader, channel).await?;
+        }
+        match found {
+            Some(candidate) => report.ported.push(make_update(index, entry, candidate)),
+            None => {
+                event!(
+                    Level::WARN,
+                    "No port of {} for {}",
+                    entry.label(),
+                    minecraft
+                );
+                report.missing.push(entry.into());
+            }
+        }
+    }
+    Ok(report)
+}
+
+/**
+ * Moves the pack to the report's target, entries without a port are dropped
+ */
+pub fn apply(pack: &mut Pack, report: &PortReport) {
+    outdated::apply(pack, &report.ported);
+    let ported: Vec<usize> = report.ported.iter().map(|u| u.index).collect();
+    let mut index = 0;
+    pack.entries.retain(|_| {
+        index += 1;
+        ported.contains(&(index - 1))
+    });
+    pack.minecraft = report.target.minecraft.clone();
+    pack.loaders.clear();
+    pack.loaders.insert(
+        report.target.loader.clone(),
+        report.target.loader_version.clone(),
+    );
+}
+
+impl Display for PortReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Porting to minecraft {} with {} {}",
+            self.target.minecraft, self.target.loader, self.target.loader_version
+        )?;
+        for update in &self.ported {
+            writeln!(f, "~ {}", update)?;
+        }
+        for entry in &self.missing {
+            writeln!(f, "! no port for {}", entry)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/**
+ * Where a long running conversion is at
+ */
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "phase")]
+pub enum Progress {
+    Reading,
+    /// Looked up `done` of `total` entries on `platform`
+    Resolving {
+        platform: String,
+        done: usize,
+        total: usize,
+    },
+    /// `bytes` of `total_bytes` downloaded so far, `total_bytes` only counts files with a known size
+    Downloading {
+        name: String,
+        file: usize,
+        files: usize,
+        bytes: u64,
+        total_bytes: u64,
+    },
+    Writing,
+    Done,
+}
+
+/**
+ * Sending half of a progress channel, see [channel]
+ */
+pub type ProgressSender = UnboundedSender<Progress>;
+
+/**
+ * Creates a channel [crate::packs::Pack::with_progress] reports into
+ */
+pub fn channel() -> (ProgressSender, UnboundedReceiver<Progress>) {
+    unbounded_channel()
+}
+
+/**
+ * Sends `progress` if anyone listens, a dropped receiver is not an error
+ */
+pub fn report(sender: &Option<ProgressSender>, progress: Progress) {
+    if let Some(sender) = sender {
+        let _ = sender.send(progress);
+    }
+}
+
+impl Progress {
+    /**
+     * Name of the phase this event belongs to, resolving is split per platform
+     */
+    pub fn phase(&self) -> String {
+        match self {
+            Progress::Reading => "reading".to_string(),
+            Progress::Resolving { platform, .. } => format!("resolving {}", platform),
+            Progress::Downloading { .. } => "downloading".to_string(),
+            Progress::Writing => "writing".to_string(),
+            Progress::Done => "done".to_string(),
+        }
+    }
+}
+
+/**
+ * How long a phase took
+ */
+#[derive(Serialize, Debug, Clone)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub seconds: f64,
+}
+/**
+ * Times phases from the events passing through it, a phase lasts until the next one starts
+ */
+#[derive(Debug, Default)]
+pub struct PhaseTimer {
+    current: Option<(String, Instant)>,
+    phases: Vec<PhaseTiming>,
+}
+impl PhaseTimer {
+    pub fn observe(&mut self, progress: &Progress) {
+        let phase = progress.phase();
+        if self.current.as_ref().is_some_and(|(p, _)| *p == phase) {
+            return;
+        }
+        self.close();
+        if *progress != Progress::Done {
+            self.current = Some((phase, Instant::now()));
+        }
+    }
+    fn close(&mut self) {
+        if let Some((phase, start)) = self.current.take() {
+            let elapsed = start.elapsed();
+            match self.phases.iter_mut().find(|t| t.phase == phase) {
+                Some(timing) => timing.seconds += elapsed.as_secs_f64(),
+                None => self.phases.push(PhaseTiming {
+                    phase,
+                    seconds: elapsed.as_secs_f64(),
+                }),
+            }
+        }
+    }
+    /**
+     * Every phase seen so far in the order it first started, ending the running one
+     */
+    pub fn finish(mut self) -> Vec<PhaseTiming> {
+        self.close();
+        self.phases
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    OutputFormat,
+    dedupe::DedupeReport,
+    jar,
+    packs::{
+        CurseRef, ModrinthRef, Pack, PackEntry,
+        modrinth_pack::{can_link, is_allowed_download},
+    },
+    progress::PhaseTiming,
+};
+
+/**
+ * Machine readable summary of a conversion
+ */
+#[derive(Serialize, Debug)]
+pub struct ConversionReport {
+    pub name: String,
+    pub version: String,
+    pub minecraft: String,
+    pub loaders: BTreeMap<String, String>,
+    pub format: String,
+    pub entries: Vec<EntryReport>,
+    pub duplicates: Option<DedupeReport>,
+    pub timings: Vec<PhaseTiming>,
+    /// Set when the conversion failed, the report then shows how far it got
+    pub error: Option<String>,
+}
+/**
+ * How a [PackEntry] ended up in the output
+ */
+#[derive(Serialize, Debug)]
+pub struct EntryReport {
+    pub name: String,
+    pub path: String,
+    pub source: EntrySource,
+    pub sha1: Option<String>,
+    pub sha512: Option<String>,
+    pub file_size: Option<u64>,
+    pub curse: Option<CurseRef>,
+    pub modrinth: Option<ModrinthRef>,
+    pub warnings: Vec<String>,
+}
+/**
+ * Where the output gets an entry's file from
+ */
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntrySource {
+    /// Downloaded from the modrinth cdn
+    Modrinth,
+    /// Downloaded from the curseforge cdn
+    Curseforge,
+    /// File carried inside the output archive
+    Embedded,
+    /// Neither a download nor the file is available
+    Missing,
+}
+
+impl EntrySource {
+    /**
+     * Source of `entry` once written as `format`, mrpacks and curseforge packs embed everything their platform cannot
+     * link to and the other formats carry every file
+     */
+    pub fn of(entry: &PackEntry, format: &OutputFormat) -> Self {
+        let source = |url: &String| {
+            let url = reqwest::Url::parse(url).ok()?;
+            let host = url.host_str()?;
+            let on = |domain: &str| {
+                host == domain
+                    || host
+                        .strip_suffix(domain)
+                        .is_some_and(|sub| sub.ends_with('.'))
+            };
+            if on("modrinth.com") {
+                Some(EntrySource::Modrinth)
+            } else if on("forgecdn.net") {
+                Some(EntrySource::Curseforge)
+            } else {
+                None
+            }
+        };
+        let linked = entry.downloads.first().and_then(source);
+        match format {
+            OutputFormat::MODRINTH if can_link(entry) => entry
+                .downloads
+                .iter()
+                .find(|url| is_allowed_download(url))
+                .and_then(source)
+                .unwrap_or(EntrySource::Modrinth),
+            OutputFormat::CURSEFORGE if entry.curse.is_some() => EntrySource::Curseforge,
+            _ if entry.contents.is_some() => EntrySource::Embedded,
+            OutputFormat::MODRINTH | OutputFormat::ATLAUNCHER | OutputFormat::GDLAUNCHER => {
+                EntrySource::Missing
+            }
+            _ => linked.unwrap_or(EntrySource::Missing),
+        }
+    }
+}
+
+impl EntryReport {
+    pub fn new(entry: &PackEntry, format: &OutputFormat) -> Self {
+        let mut warnings = entry.warnings.clone();
+        if entry.env.is_none()
+            && let Some(contents) = &entry.contents
+            && let Ok(mods) = jar::inspect(contents)
+            && let Some(env) = mods.iter().find_map(|m| m.env.as_ref())
+            && env.server == "unsupported"
+        {
+            warnings.push(
+                "Client only according to its jar metadata but no environment is set".to_string(),
+            );
+        }
+        Self {
+            name: entry.label(),
+            path: entry.path.clone(),
+            source: EntrySource::of(entry, format),
+            sha1: entry.sha1.clone(),
+            sha512: entry.sha512.clone(),
+            file_size: entry.file_size,
+            curse: entry.curse.clone(),
+            modrinth: entry.modrinth.clone(),
+            warnings,
+        }
+    }
+}
+
+impl ConversionReport {
+    /**
+     * Report of `pack` as written in `format`
+     */
+    pub fn new(
+        pack: &Pack,
+        format: &OutputFormat,
+        duplicates: Option<DedupeReport>,
+        timings: Vec<PhaseTiming>,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            name: pack.name.clone(),
+            version: pack.version.clone(),
+            minecraft: pack.minecraft.clone(),
+            loaders: pack.loaders.clone(),
+            format: format!("{:?}", format),
+            entries: pack
+                .entries
+                .iter()
+                .map(|e| EntryReport::new(e, format))
+                .collect(),
+            duplicates,
+            timings,
+            error,
+        }
+    }
+}
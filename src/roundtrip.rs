@@ -0,0 +1,251 @@
+use std::{
+    fmt::Display,
+    io::{Cursor, Read, Seek},
+};
+
+use color_eyre::{Result, eyre::Context};
+use serde::Serialize;
+use zip::ZipArchive;
+
+use crate::{
+    OutputFormat,
+    diff::{self, PackDiff, same_mod},
+    packs::{self, Compression, Pack, PackEntry, modrinth_pack::DownloadPolicy, sha1_hex},
+    platforms::ApiClient,
+    write_pack,
+};
+
+/**
+ * Outcome of [roundtrip]
+ */
+#[derive(Serialize, Debug)]
+pub struct RoundtripReport {
+    pub via: String,
+    pub back: String,
+    pub steps: Vec<Step>,
+    /// Original against the pack that came back, by mod identity and override content
+    pub diff: PackDiff,
+}
+/**
+ * One conversion of a [roundtrip] and what it could not carry
+ */
+#[derive(Serialize, Debug)]
+pub struct Step {
+    pub from: String,
+    pub to: String,
+    pub losses: Vec<String>,
+}
+
+impl RoundtripReport {
+    pub fn is_lossless(&self) -> bool {
+        self.steps.iter().all(|s| s.losses.is_empty()) && self.diff.is_empty()
+    }
+}
+
+/**
+ * Reads a pack, writes it as `via`, reads that back and writes it in the original format again, reporting what every
+ * step lost
+ *
+ * Every pack along the way is resolved on curseforge and modrinth so entries are compared by identity
+ */
+pub async fn roundtrip<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    via: &OutputFormat,
+    compression: &Compression,
+    client: &ApiClient,
+) -> Result<RoundtripReport> {
+    let back = OutputFormat::same_as(packs::detect_format(archive))?;
+    let mut original = packs::read_archive(archive)?;
+    resolve(&mut original, client).await?;
+    let mut steps = Vec::new();
+
+    let mut current = original.clone();
+    let mut from = back.clone();
+    for format in [via, &back] {
+        let mut written = current.clone();
+        let mut out = Cursor::new(Vec::new());
+        write_pack(
+            &mut written,
+            format,
+            &mut out,
+            compression,
+            DownloadPolicy::default(),
+            client,
+        )
+        .await
+        .wrap_err(format!("Err while writing {:?}", format))?;
+        let mut next = packs::read_archive(
+            &mut ZipArchive::new(Cursor::new(out.into_inner()))
+                .wrap_err("Err while reading written pack")?,
+        )
+        .wrap_err(format!("Err while reading back {:?}", format))?;
+        resolve(&mut next, client).await?;
+        steps.push(Step {
+            from: format!("{:?}", from),
+            to: format!("{:?}", format),
+            losses: losses(&written, &next),
+        });
+        current = next;
+        from = format.clone();
+    }
+
+    Ok(RoundtripReport {
+        via: format!("{:?}", via),
+        back: format!("{:?}", back),
+        steps,
+        diff: diff::diff(&original, &current),
+    })
+}
+async fn resolve(pack: &mut Pack, client: &ApiClient) -> Result<()> {
+    pack.resolve_curse(client).await?;
+    pack.resolve_modrinth(client).await
+}
+
+/**
+ * Everything in `before` that did not make it into `after` unchanged
+ */
+pub fn losses(before: &Pack, after: &Pack) -> Vec<String> {
+    let mut out = Vec::new();
+    for (field, from, to) in [
+        ("name", &before.name, &after.name),
+        ("version", &before.version, &after.version),
+        ("author", &before.author, &after.author),
+        ("summary", &before.summary, &after.summary),
+        ("minecraft version", &before.minecraft, &after.minecraft),
+    ] {
+        if from != to {
+            out.push(format!("Pack {} {:?} became {:?}", field, from, to));
+        }
+    }
+    if before.loaders != after.loaders {
+        out.push(format!(
+            "Loaders {:?} became {:?}",
+            before.loaders, after.loaders
+        ));
+    }
+    if before.overrides_folder != after.overrides_folder {
+        out.push(format!(
+            "Overrides folder {} became {}",
+            before.overrides_folder.as_deref().unwrap_or("overrides"),
+            after.overrides_folder.as_deref().unwrap_or("overrides")
+        ));
+    }
+
+    let mut embedded = Vec::new();
+    let mut unmatched: Vec<&PackEntry> = after.entries.iter().collect();
+    for entry in &before.entries {
+        let Some(index) = unmatched.iter().position(|e| same_mod(entry, e)) else {
+            let override_path = entry.contents.as_ref().and_then(|contents| {
+                let sha1 = sha1_hex(contents);
+                after
+                    .overrides
+                    .common
+                    .iter()
+                    .find(|(_, data)| sha1_hex(data) == sha1)
+                    .map(|(path, _)| path.clone())
+            });
+            match override_path {
+                Some(path) => {
+                    out.push(format!(
+                        "{} was embedded into overrides as {}",
+                        entry.label(),
+                        path
+                    ));
+                    embedded.push(path);
+                }
+                None => out.push(format!("{} was dropped", entry.label())),
+            }
+            continue;
+        };
+        let other = unmatched.remove(index);
+        if entry.sha1 != other.sha1 {
+            out.push(format!("{} changed to {}", entry.label(), other.label()));
+        }
+        if entry.required != other.required {
+            out.push(format!(
+                "{} became {}",
+                entry.label(),
+                if other.required {
+                    "required"
+                } else {
+                    "optional"
+                }
+            ));
+        }
+        if entry.env.as_ref().map(|e| (&e.client, &e.server))
+            != other.env.as_ref().map(|e| (&e.client, &e.server))
+        {
+            out.push(format!("{} lost its environment", entry.label()));
+        }
+        if entry.curse.is_some() && other.curse.is_none() {
+            out.push(format!("{} lost its curseforge file", entry.label()));
+        }
+        if entry.modrinth.is_some() && other.modrinth.is_none() {
+            out.push(format!("{} lost its modrinth version", entry.label()));
+        }
+    }
+    for entry in unmatched {
+        out.push(format!("{} appeared", entry.label()));
+    }
+
+    for (side, from, to) in [
+        (
+            "overrides",
+            &before.overrides.common,
+            &after.overrides.common,
+        ),
+        (
+            "client overrides",
+            &before.overrides.client,
+            &after.overrides.client,
+        ),
+        (
+            "server overrides",
+            &before.overrides.server,
+            &after.overrides.server,
+        ),
+    ] {
+        for (path, data) in from {
+            match to.get(path) {
+                Some(other) if other == data => {}
+                Some(_) => out.push(format!("{} {} changed", side, path)),
+                // formats without side specific overrides merge them into the common ones
+                None if before.overrides.common.get(path) != Some(data)
+                    && after.overrides.common.get(path) == Some(data) =>
+                {
+                    out.push(format!("{} {} became a common override", side, path));
+                }
+                None => out.push(format!("{} {} was dropped", side, path)),
+            }
+        }
+        for path in to.keys().filter(|p| !from.contains_key(*p)) {
+            let merged = side == "overrides"
+                && [&before.overrides.client, &before.overrides.server]
+                    .iter()
+                    .any(|o| o.contains_key(path));
+            if !embedded.contains(path) && !merged {
+                out.push(format!("{} {} appeared", side, path));
+            }
+        }
+    }
+    out
+}
+
+impl Display for RoundtripReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{} -> {}", step.from, step.to)?;
+            if step.losses.is_empty() {
+                writeln!(f, "  lossless")?;
+            }
+            for loss in &step.losses {
+                writeln!(f, "  {}", loss)?;
+            }
+        }
+        writeln!(f, "Original -> {}", self.back)?;
+        for line in self.diff.to_string().lines() {
+            writeln!(f, "  {}", line)?;
+        }
+        Ok(())
+    }
+}
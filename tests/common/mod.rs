@@ -0,0 +1,175 @@
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fs,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use http_body_util::Full;
+use hyper::{
+    Request, Response, StatusCode,
+    body::{Bytes, Incoming},
+    server::conn::http1,
+    service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use multipack::{
+    build_client,
+    platforms::{ApiClient, ApiUrls},
+};
+use tokio::net::TcpListener;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+/**
+ * `tests/fixtures`
+ */
+pub fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/**
+ * Starts the mock api once per test binary, returns its base url
+ *
+ * `GET /a/b` answers with `tests/fixtures/api/a/b.json` (`{{base}}` replaced by the base url) or the raw file
+ * `tests/fixtures/api/a/b`, anything else is a 404. The query string and method are ignored, so the curse fingerprint
+ * lookup is `api/v1/fingerprints.json`
+ */
+pub fn mock_api() -> &'static str {
+    static BASE: OnceLock<String> = OnceLock::new();
+    BASE.get_or_init(|| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        // a thread of its own so the server outlives the runtime of the test that started it
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("mock api runtime");
+            runtime.block_on(async move {
+                let listener = TcpListener::bind("127.0.0.1:0")
+                    .await
+                    .expect("mock api port");
+                let base = format!("http://{}", listener.local_addr().expect("mock api addr"));
+                tx.send(base.clone()).expect("mock api base");
+                loop {
+                    let Ok((stream, _)) = listener.accept().await else {
+                        continue;
+                    };
+                    let base = base.clone();
+                    tokio::spawn(async move {
+                        let service = service_fn(move |req| respond(req, base.clone()));
+                        let _ = http1::Builder::new()
+                            .serve_connection(TokioIo::new(stream), service)
+                            .await;
+                    });
+                }
+            });
+        });
+        rx.recv().expect("mock api did not start")
+    })
+}
+
+/**
+ * A client talking to the [mock_api] for both platforms
+ */
+pub fn client() -> ApiClient {
+    let base = mock_api();
+    build_client("test-key")
+        .expect("test client")
+        .with_urls(ApiUrls {
+            curse: base.to_string(),
+            modrinth: base.to_string(),
+        })
+}
+
+async fn respond(
+    req: Request<Incoming>,
+    base: String,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = req.uri().path().trim_start_matches('/');
+    let root = fixtures().join("api");
+    let reply = |status: StatusCode, content_type: &str, body: Vec<u8>| {
+        Response::builder()
+            .status(status)
+            .header("content-type", content_type)
+            .body(Full::new(Bytes::from(body)))
+            .expect("mock api response")
+    };
+    if path.split('/').any(|part| part == "..") {
+        return Ok(reply(StatusCode::BAD_REQUEST, "text/plain", Vec::new()));
+    }
+    if let Ok(body) = fs::read_to_string(root.join(format!("{}.json", path))) {
+        return Ok(reply(
+            StatusCode::OK,
+            "application/json",
+            body.replace("{{base}}", &base).into_bytes(),
+        ));
+    }
+    Ok(match fs::read(root.join(path)) {
+        Ok(body) => reply(StatusCode::OK, "application/java-archive", body),
+        Err(_) => reply(StatusCode::NOT_FOUND, "text/plain", Vec::new()),
+    })
+}
+
+/**
+ * Zips `tests/fixtures/packs/<name>`, filling in `{{base}}` of the mock api in json files
+ */
+pub fn pack(name: &str) -> Vec<u8> {
+    let root = fixtures().join("packs").join(name);
+    let mut files = Vec::new();
+    walk(&root, &mut files);
+    files.sort();
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for file in files {
+        let name = file
+            .strip_prefix(&root)
+            .expect("fixture below its pack")
+            .to_string_lossy()
+            .replace('\\', "/");
+        let mut data = fs::read(&file).expect("fixture file");
+        if name.ends_with(".json") {
+            data = String::from_utf8(data)
+                .expect("utf8 fixture")
+                .replace("{{base}}", mock_api())
+                .into_bytes();
+        }
+        writer
+            .start_file(name, SimpleFileOptions::default())
+            .expect("fixture zip entry");
+        writer.write_all(&data).expect("fixture zip write");
+    }
+    writer.finish().expect("fixture zip").into_inner()
+}
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).expect("fixture dir") {
+        let path = entry.expect("fixture dir entry").path();
+        if path.is_dir() {
+            walk(&path, out);
+        } else {
+            out.push(path);
+        }
+    }
+}
+
+/**
+ * Every file of a written archive keyed by its name
+ */
+pub fn unzip(data: &[u8]) -> BTreeMap<String, Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).expect("output is a zip");
+    let mut out = BTreeMap::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).expect("output zip entry");
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).expect("output zip read");
+        out.insert(file.name().to_string(), buf);
+    }
+    out
+}
+
+/**
+ * Downloadable file served by the mock api
+ */
+pub fn served_file(name: &str) -> Vec<u8> {
+    fs::read(fixtures().join("api/files").join(name)).expect("served fixture file")
+}
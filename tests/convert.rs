@@ -0,0 +1,1373 @@
+mod common;
+
+use std::{
+    fs,
+    io::{Cursor, Write},
+    path::PathBuf,
+};
+
+use multipack::{
+    ConvertOptions, OutputFormat, build_client, convert,
+    dedupe::{self, DuplicatePolicy, compare_versions},
+    deps, diff,
+    install::{self, Lockfile, OptionalMods, Side},
+    jar, outdated,
+    packs::{
+        self, ArchiveLimits, Compression, CurseRef, DependencyKind, MetadataOverrides, ModrinthRef,
+        Pack, PackEntry,
+        modrinth_pack::{self, DownloadPolicy},
+    },
+    platforms::{ApiUrls, curse::ReleaseType},
+    port::{self, PortTarget},
+    progress::{self, PhaseTimer, Progress},
+    report::EntrySource,
+    roundtrip::roundtrip,
+    write_pack,
+};
+use serde_json::Value;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+async fn run(pack: &str, format: OutputFormat) -> color_eyre::Result<(Pack, Vec<u8>)> {
+    let opts = ConvertOptions::new(format, common::client());
+    let mut output = Cursor::new(Vec::new());
+    let conversion = convert(Cursor::new(common::pack(pack)), &mut output, &opts).await?;
+    Ok((conversion.pack, output.into_inner()))
+}
+
+#[tokio::test]
+async fn curse_to_modrinth_links_hosted_files_and_embeds_the_rest() {
+    let (pack, output) = run("curse", OutputFormat::MODRINTH).await.unwrap();
+    assert_eq!(pack.name, "Test Pack");
+    let files = common::unzip(&output);
+
+    let index: Value = serde_json::from_slice(&files["modrinth.index.json"]).unwrap();
+    assert_eq!(index["dependencies"]["minecraft"], "1.20.1");
+    assert_eq!(index["dependencies"]["fabric-loader"], "0.15.11");
+    let linked = index["files"].as_array().unwrap();
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0]["path"], "mods/beta-2.0.0.jar");
+    assert_eq!(
+        linked[0]["downloads"][0],
+        "https://cdn.modrinth.com/data/BetaProj/versions/BetaV200/beta-2.0.0.jar"
+    );
+    assert_eq!(
+        linked[0]["env"],
+        serde_json::json!({ "client": "optional", "server": "optional" })
+    );
+
+    assert_eq!(
+        files["overrides/mods/alpha-1.0.0.jar"],
+        common::served_file("alpha-1.0.0.jar")
+    );
+    assert_eq!(files["overrides/config/test.txt"], b"option=true\n");
+    assert!(!files.contains_key("overrides/mods/beta-2.0.0.jar"));
+}
+
+#[tokio::test]
+async fn curse_to_atlauncher_downloads_every_file() {
+    let (_, output) = run("curse-open", OutputFormat::ATLAUNCHER).await.unwrap();
+    let files = common::unzip(&output);
+    assert!(files.contains_key("instance.json"));
+    assert_eq!(
+        files["mods/alpha-1.0.0.jar"],
+        common::served_file("alpha-1.0.0.jar")
+    );
+    assert_eq!(files["config/test.txt"], b"option=true\n");
+}
+
+#[tokio::test]
+async fn curse_file_without_download_url_fails_launcher_output() {
+    let err = run("curse", OutputFormat::ATLAUNCHER).await.unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("No download url available for Beta 2.0.0"),
+        "{:#}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn each_client_talks_to_its_own_api() {
+    let offline = build_client("test-key").unwrap().with_urls(ApiUrls {
+        curse: "http://127.0.0.1:1".to_string(),
+        modrinth: "http://127.0.0.1:1".to_string(),
+    });
+    let opts = ConvertOptions::new(OutputFormat::MODRINTH, offline);
+    assert!(
+        convert(
+            Cursor::new(common::pack("curse")),
+            Cursor::new(Vec::new()),
+            &opts
+        )
+        .await
+        .is_err()
+    );
+    run("curse", OutputFormat::MODRINTH).await.unwrap();
+}
+
+#[tokio::test]
+async fn modrinth_to_gdlauncher_downloads_from_the_index() {
+    let (pack, output) = run("modrinth", OutputFormat::GDLAUNCHER).await.unwrap();
+    assert_eq!(pack.summary, "A pack for tests");
+    let files = common::unzip(&output);
+    assert!(files.contains_key("config.json"));
+    assert_eq!(
+        files["mods/beta-2.0.0.jar"],
+        common::served_file("beta-2.0.0.jar")
+    );
+    assert_eq!(files["config/test.txt"], b"option=true\n");
+}
+
+#[tokio::test]
+async fn curse_shader_packs_go_to_shaderpacks() {
+    let (pack, output) = run("curse-shaders", OutputFormat::GDLAUNCHER)
+        .await
+        .unwrap();
+    assert_eq!(pack.entries[1].path, "shaderpacks/gamma-shaders-1.0.zip");
+    let files = common::unzip(&output);
+    assert_eq!(
+        files["shaderpacks/gamma-shaders-1.0.zip"],
+        common::served_file("gamma-shaders-1.0.zip")
+    );
+    assert!(!files.contains_key("mods/gamma-shaders-1.0.zip"));
+    let config: Value = serde_json::from_slice(&files["config.json"]).unwrap();
+    let listed: Vec<_> = config["mods"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["fileName"].as_str().unwrap())
+        .collect();
+    assert_eq!(listed, ["alpha-1.0.0.jar"]);
+
+    let (_, output) = run("curse-shaders", OutputFormat::MODRINTH).await.unwrap();
+    let files = common::unzip(&output);
+    assert!(files.contains_key("overrides/shaderpacks/gamma-shaders-1.0.zip"));
+}
+
+#[tokio::test]
+async fn modrinth_files_on_other_hosts_are_embedded_or_refused() {
+    let (_, output) = run("modrinth-external", OutputFormat::MODRINTH)
+        .await
+        .unwrap();
+    let files = common::unzip(&output);
+    let index: Value = serde_json::from_slice(&files["modrinth.index.json"]).unwrap();
+    assert_eq!(index["files"].as_array().unwrap().len(), 0);
+    assert_eq!(
+        files["overrides/mods/alpha-1.0.0.jar"],
+        common::served_file("alpha-1.0.0.jar")
+    );
+
+    let mut opts = ConvertOptions::new(OutputFormat::MODRINTH, common::client());
+    opts.downloads = DownloadPolicy::Error;
+    let err = convert(
+        Cursor::new(common::pack("modrinth-external")),
+        Cursor::new(Vec::new()),
+        &opts,
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("alpha-1.0.0.jar is only available from http://127.0.0.1"),
+        "{:#}",
+        err
+    );
+
+    // a curseforge file on a host modrinth packs may not link to
+    let err = convert(
+        Cursor::new(common::pack("curse-open")),
+        Cursor::new(Vec::new()),
+        &opts,
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("Alpha 1.0.0 is only available from http://127.0.0.1"),
+        "{:#}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn curse_files_on_allowed_hosts_are_linked() {
+    let mut pack = downloaded("curse-open").await;
+    let alpha = &mut pack.entries[0];
+    assert_eq!(
+        alpha.sha512.as_deref(),
+        Some(packs::sha512_hex(&common::served_file("alpha-1.0.0.jar")).as_str())
+    );
+    let url = "https://edge.forgecdn.net/files/1000/0/alpha-1.0.0.jar".to_string();
+    alpha.downloads = vec![url.clone()];
+    let unhashed = PackEntry {
+        sha512: None,
+        contents: None,
+        ..alpha.clone()
+    };
+    for policy in [DownloadPolicy::Embed, DownloadPolicy::Error] {
+        assert!(policy.needs_contents(&unhashed), "{:?}", policy);
+        let mut out = ZipWriter::new(Cursor::new(Vec::new()));
+        modrinth_pack::write(&pack, &mut out, &Compression::default(), policy).unwrap();
+        let files = common::unzip(&out.finish().unwrap().into_inner());
+        let index: Value = serde_json::from_slice(&files["modrinth.index.json"]).unwrap();
+        assert_eq!(
+            index["files"][0]["downloads"][0],
+            url.as_str(),
+            "{:?}",
+            policy
+        );
+        assert!(!files.contains_key("overrides/mods/alpha-1.0.0.jar"));
+    }
+}
+
+#[tokio::test]
+async fn curse_to_curse_keeps_the_manifest_and_lists_mods() {
+    let (_, output) = run("curse", OutputFormat::CURSEFORGE).await.unwrap();
+    let files = common::unzip(&output);
+    let manifest: Value = serde_json::from_slice(&files["manifest.json"]).unwrap();
+    assert_eq!(manifest["manifestType"], "minecraftModpack");
+    assert_eq!(manifest["manifestVersion"], 1);
+    assert_eq!(manifest["overrides"], "overrides");
+    assert_eq!(
+        manifest["minecraft"]["modLoaders"][0]["id"],
+        "fabric-0.15.11"
+    );
+    assert_eq!(manifest["files"][1]["projectID"], 200);
+    assert_eq!(manifest["files"][1]["fileID"], 2000);
+    assert_eq!(manifest["files"][1]["required"], false);
+    let modlist = String::from_utf8(files["modlist.html"].clone()).unwrap();
+    assert!(
+        modlist.contains(
+            r#"<li><a href="https://www.curseforge.com/minecraft/mc-mods/beta">Beta (by tester)</a></li>"#
+        ),
+        "{}",
+        modlist
+    );
+    assert_eq!(files["overrides/config/test.txt"], b"option=true\n");
+    assert!(!files.keys().any(|k| k.ends_with(".jar")));
+}
+
+#[tokio::test]
+async fn modrinth_to_curse_embeds_files_without_a_curse_file() {
+    let (_, output) = run("modrinth", OutputFormat::CURSEFORGE).await.unwrap();
+    let files = common::unzip(&output);
+    let manifest: Value = serde_json::from_slice(&files["manifest.json"]).unwrap();
+    assert_eq!(manifest["files"].as_array().unwrap().len(), 0);
+    assert_eq!(
+        files["overrides/mods/beta-2.0.0.jar"],
+        common::served_file("beta-2.0.0.jar")
+    );
+    assert_eq!(files["modlist.html"], b"<ul>\n</ul>\n");
+
+    // alpha is known to curseforge by its fingerprint
+    let (_, output) = run("modrinth-external", OutputFormat::CURSEFORGE)
+        .await
+        .unwrap();
+    let files = common::unzip(&output);
+    let manifest: Value = serde_json::from_slice(&files["manifest.json"]).unwrap();
+    assert_eq!(manifest["files"][0]["projectID"], 100);
+    assert_eq!(manifest["files"][0]["fileID"], 1000);
+    assert!(!files.contains_key("overrides/mods/alpha-1.0.0.jar"));
+}
+
+#[tokio::test]
+async fn metadata_without_a_field_survives_a_round_trip() {
+    let client = common::client();
+    let mut opts = ConvertOptions::new(OutputFormat::MODRINTH, client.clone());
+    opts.metadata = MetadataOverrides {
+        version: Some("1.0.1".to_string()),
+        summary: Some("Renamed for tests".to_string()),
+        ..Default::default()
+    };
+    let mut mrpack = Cursor::new(Vec::new());
+    convert(Cursor::new(common::pack("curse-open")), &mut mrpack, &opts)
+        .await
+        .unwrap();
+    let files = common::unzip(mrpack.get_ref());
+    let sidecar: Value = serde_json::from_slice(&files["multipack.json"]).unwrap();
+    assert_eq!(sidecar, serde_json::json!({ "author": "tester" }));
+
+    let opts = ConvertOptions::new(OutputFormat::CURSEFORGE, client);
+    let mut curse = Cursor::new(Vec::new());
+    let pack = convert(Cursor::new(mrpack.into_inner()), &mut curse, &opts)
+        .await
+        .unwrap()
+        .pack;
+    assert_eq!(pack.author, "tester");
+    assert_eq!(pack.version, "1.0.1");
+    assert!(!pack.overrides.common.contains_key("multipack.json"));
+    let files = common::unzip(curse.get_ref());
+    let manifest: Value = serde_json::from_slice(&files["manifest.json"]).unwrap();
+    assert_eq!(manifest["author"], "tester");
+    assert_eq!(manifest["version"], "1.0.1");
+    let sidecar: Value = serde_json::from_slice(&files["multipack.json"]).unwrap();
+    assert_eq!(
+        sidecar,
+        serde_json::json!({ "summary": "Renamed for tests" })
+    );
+}
+
+#[tokio::test]
+async fn roundtrip_reports_what_modrinth_cannot_carry() {
+    let client = common::client();
+    let mut archive = ZipArchive::new(Cursor::new(common::pack("curse-custom"))).unwrap();
+    let report = roundtrip(
+        &mut archive,
+        &OutputFormat::MODRINTH,
+        &Compression::default(),
+        &client,
+    )
+    .await
+    .unwrap();
+    assert!(!report.is_lossless());
+    assert_eq!(
+        report.steps[0].losses,
+        [
+            "Overrides folder minecraft became overrides",
+            "Alpha 1.0.0 was embedded into overrides as mods/alpha-1.0.0.jar",
+        ]
+    );
+    assert!(report.steps[1].losses.is_empty(), "{:?}", report.steps[1]);
+    assert_eq!(report.diff.removed[0].name, "Alpha 1.0.0");
+    assert_eq!(
+        report.diff.overrides.added,
+        ["overrides/mods/alpha-1.0.0.jar"]
+    );
+}
+
+/**
+ * Empty directory of its own below the system temp dir
+ */
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("multipack-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+async fn downloaded(name: &str) -> Pack {
+    let client = common::client();
+    let mut pack =
+        packs::read_archive(&mut ZipArchive::new(Cursor::new(common::pack(name))).unwrap())
+            .unwrap();
+    pack.resolve_curse(&client).await.unwrap();
+    pack.fetch_contents(&client, |_| true).await.unwrap();
+    pack
+}
+
+#[tokio::test]
+async fn downloads_are_checked_against_both_hashes() {
+    let client = common::client();
+    let read = || {
+        packs::read_archive(&mut ZipArchive::new(Cursor::new(common::pack("modrinth"))).unwrap())
+            .unwrap()
+    };
+    let mut pack = read();
+    pack.fetch_contents(&client, |_| true).await.unwrap();
+    assert!(pack.entries.iter().all(|e| e.contents.is_some()));
+
+    let mut pack = read();
+    let entry = pack
+        .entries
+        .iter_mut()
+        .find(|e| e.sha512.is_some())
+        .unwrap();
+    entry.sha512 = Some("0".repeat(128));
+    let label = entry.label();
+    let err = pack.fetch_contents(&client, |_| true).await.unwrap_err();
+    assert!(
+        format!("{:#}", err).contains(&format!("Sha512 mismatch: expected {}", "0".repeat(128))),
+        "{:#}",
+        err
+    );
+    assert!(format!("{:#}", err).ends_with(&label), "{:#}", err);
+}
+
+#[tokio::test]
+async fn optional_mods_are_left_out_unless_selected() {
+    let pack = downloaded("curse-custom").await;
+    assert!(!pack.entries[0].required);
+
+    let dir = scratch("optional-none");
+    let lock = install::install(&pack, &dir, Side::Client, &OptionalMods::None).unwrap();
+    assert!(lock.entries.is_empty());
+    assert!(!dir.join("mods/alpha-1.0.0.jar").exists());
+    assert_eq!(
+        fs::read(dir.join("config/test.txt")).unwrap(),
+        b"option=true\n"
+    );
+
+    let dir = scratch("optional-only");
+    let selection = OptionalMods::parse("alpha 1.0.0, other.jar");
+    assert_eq!(
+        selection,
+        OptionalMods::Only(vec!["alpha 1.0.0".to_string(), "other.jar".to_string()])
+    );
+    install::install(&pack, &dir, Side::Client, &selection).unwrap();
+    assert_eq!(
+        fs::read(dir.join("mods/alpha-1.0.0.jar")).unwrap(),
+        common::served_file("alpha-1.0.0.jar")
+    );
+    assert_eq!(Lockfile::load(&dir).unwrap().optional, selection);
+}
+
+#[tokio::test]
+async fn update_keeps_overrides_the_user_edited() {
+    let mut pack = downloaded("curse-open").await;
+    pack.overrides
+        .common
+        .insert("config/other.txt".to_string(), b"a=1\n".to_vec());
+    let dir = scratch("update-edited");
+    install::install(&pack, &dir, Side::Client, &OptionalMods::All).unwrap();
+    assert!(dir.join("mods/alpha-1.0.0.jar").exists());
+    fs::write(dir.join("config/test.txt"), b"option=false\n").unwrap();
+
+    let mut next = downloaded("curse-open").await;
+    next.version = "1.1.0".to_string();
+    next.entries.clear();
+    next.overrides
+        .common
+        .insert("config/test.txt".to_string(), b"option=maybe\n".to_vec());
+    next.overrides
+        .common
+        .insert("config/other.txt".to_string(), b"a=2\n".to_vec());
+    let lock = install::update(&mut next, &dir, &common::client())
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read(dir.join("config/test.txt")).unwrap(),
+        b"option=false\n"
+    );
+    assert_eq!(fs::read(dir.join("config/other.txt")).unwrap(), b"a=2\n");
+    assert!(!dir.join("mods/alpha-1.0.0.jar").exists());
+    assert_eq!(lock.version, "1.1.0");
+}
+
+#[tokio::test]
+async fn update_rejects_a_lockfile_pointing_outside_the_install() {
+    let mut pack = downloaded("curse-open").await;
+    let dir = scratch("lockfile-escape");
+    let mut lock = install::install(&pack, &dir, Side::Client, &OptionalMods::None).unwrap();
+    let outside = dir.with_extension("outside");
+    fs::write(&outside, b"keep me").unwrap();
+    lock.overrides.insert(
+        format!("../{}", outside.file_name().unwrap().to_string_lossy()),
+        packs::sha1_hex(b"keep me"),
+    );
+    lock.save(&dir).unwrap();
+
+    let client = common::client();
+    let err = install::update(&mut pack, &dir, &client).await.unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("escapes the install folder"),
+        "{:#}",
+        err
+    );
+    assert_eq!(fs::read(&outside).unwrap(), b"keep me");
+}
+
+#[tokio::test]
+async fn outdated_never_offers_an_older_version() {
+    let client = common::client();
+    let read = |name| {
+        packs::read_archive(&mut ZipArchive::new(Cursor::new(common::pack(name))).unwrap()).unwrap()
+    };
+    let installed_beta = read("modrinth-beta");
+    let updates = outdated::find_updates(&installed_beta, &client, ReleaseType::Release)
+        .await
+        .unwrap();
+    assert!(updates.is_empty(), "{:?}", updates);
+
+    let mut installed_release = read("modrinth");
+    installed_release.resolve_modrinth(&client).await.unwrap();
+    let updates = outdated::find_updates(&installed_release, &client, ReleaseType::Beta)
+        .await
+        .unwrap();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].latest.version.as_deref(), Some("2.1.0"));
+}
+
+/**
+ * Zip of `files` as given, stored or deflated
+ */
+fn raw_zip(files: &[(&str, &[u8])], method: CompressionMethod) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(method);
+    for (name, data) in files {
+        writer.start_file(*name, options).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+fn check(data: Vec<u8>, limits: &ArchiveLimits) -> String {
+    let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+    match packs::check_archive(&mut archive, limits) {
+        Ok(()) => String::new(),
+        Err(e) => format!("{:#}", e),
+    }
+}
+
+#[test]
+fn archives_that_escape_or_explode_are_rejected() {
+    let limits = ArchiveLimits::default();
+    let slip = raw_zip(&[("../evil.txt", b"x")], CompressionMethod::Stored);
+    assert!(check(slip.clone(), &limits).contains("../evil.txt escapes the pack folder"));
+    let err = packs::read_archive(&mut ZipArchive::new(Cursor::new(slip)).unwrap()).unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("escapes the pack folder"),
+        "{:#}",
+        err
+    );
+    let absolute = raw_zip(&[("/etc/evil", b"x")], CompressionMethod::Stored);
+    assert!(check(absolute, &limits).contains("escapes the pack folder"));
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer
+        .add_symlink("config/link", "/etc/passwd", SimpleFileOptions::default())
+        .unwrap();
+    let symlink = writer.finish().unwrap().into_inner();
+    assert!(check(symlink, &limits).contains("config/link is a symlink"));
+
+    let three = raw_zip(
+        &[("a", b"1"), ("b", b"2"), ("c", b"3")],
+        CompressionMethod::Stored,
+    );
+    let few = ArchiveLimits {
+        max_entries: 2,
+        ..Default::default()
+    };
+    assert!(check(three.clone(), &few).contains("Archive has 3 entries, more than the limit of 2"));
+    assert_eq!(check(three, &limits), "");
+
+    let zeros = vec![0u8; 4 << 20];
+    let bomb = raw_zip(&[("zeros.bin", &zeros)], CompressionMethod::Deflated);
+    assert!(check(bomb.clone(), &limits).contains("more than 100 times"));
+    let small = ArchiveLimits {
+        max_ratio: u64::MAX,
+        max_total_size: 1 << 20,
+        ..Default::default()
+    };
+    assert!(check(bomb, &small).contains("Archive is larger than 1048576 bytes uncompressed"));
+}
+
+#[test]
+fn curse_overrides_folder_outside_the_pack_is_rejected() {
+    let mut manifest: Value = serde_json::from_slice(
+        &fs::read("tests/fixtures/packs/curse-custom/manifest.json").unwrap(),
+    )
+    .unwrap();
+    for folder in ["../x", "a/b", "/etc"] {
+        manifest["overrides"] = Value::from(folder);
+        let zip = raw_zip(
+            &[("manifest.json", &serde_json::to_vec(&manifest).unwrap())],
+            CompressionMethod::Stored,
+        );
+        let err = packs::read_archive(&mut ZipArchive::new(Cursor::new(zip)).unwrap()).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains(&format!(
+                "overrides folder {} escapes the pack folder",
+                folder
+            )),
+            "{:#}",
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn convert_reports_the_duplicates_it_dropped() {
+    let opts = ConvertOptions::new(OutputFormat::MODRINTH, common::client());
+    let mut output = Cursor::new(Vec::new());
+    let conversion = convert(
+        Cursor::new(common::pack("curse-duplicate")),
+        &mut output,
+        &opts,
+    )
+    .await
+    .unwrap();
+    let duplicates = conversion.duplicates.unwrap();
+    assert_eq!(duplicates.groups.len(), 1);
+    assert_eq!(duplicates.groups[0].dropped.len(), 1);
+    assert!(
+        !conversion
+            .pack
+            .overrides
+            .common
+            .contains_key("mods/alpha-1.0.0.jar")
+    );
+}
+
+#[tokio::test]
+async fn duplicates_in_another_version_are_found_by_fingerprint() {
+    for (policy, kept) in [
+        (DuplicatePolicy::KeepManifest, "Alpha 1.0.0"),
+        (DuplicatePolicy::KeepNewest, "Alpha 1.0.0"),
+    ] {
+        let mut opts = ConvertOptions::new(OutputFormat::CURSEFORGE, common::client());
+        opts.duplicates = policy;
+        let conversion = convert(
+            Cursor::new(common::pack("curse-duplicate-old")),
+            Cursor::new(Vec::new()),
+            &opts,
+        )
+        .await
+        .unwrap();
+        let duplicates = conversion.duplicates.unwrap();
+        assert_eq!(duplicates.groups.len(), 1, "{:?}", policy);
+        let group = &duplicates.groups[0];
+        assert_eq!(group.kept, kept);
+        assert_eq!(group.dropped, ["overrides/mods/alpha-0.9.0.jar"]);
+        assert!(group.matched_by.contains(&"curseforge project".to_string()));
+    }
+}
+
+#[test]
+fn duplicates_only_count_within_one_install() {
+    let jar = fabric_jar("alpha", "1.0.0");
+    let mut pack = Pack::default();
+    pack.overrides
+        .client
+        .insert("mods/alpha.jar".to_string(), jar.clone());
+    pack.overrides
+        .server
+        .insert("mods/alpha.jar".to_string(), jar.clone());
+    let report =
+        dedupe::dedupe(&mut pack, DuplicatePolicy::default(), &Default::default()).unwrap();
+    assert!(report.groups.is_empty(), "{}", report);
+    assert_eq!(pack.overrides.client.len(), 1);
+    assert_eq!(pack.overrides.server.len(), 1);
+
+    // a common copy meets both sides
+    pack.overrides
+        .common
+        .insert("mods/alpha-common.jar".to_string(), jar);
+    let report =
+        dedupe::dedupe(&mut pack, DuplicatePolicy::default(), &Default::default()).unwrap();
+    assert_eq!(report.groups.len(), 1, "{}", report);
+    assert_eq!(report.groups[0].kept, "overrides/mods/alpha-common.jar");
+    assert!(pack.overrides.client.is_empty() && pack.overrides.server.is_empty());
+}
+
+#[test]
+fn keep_newest_compares_versions_by_segment() {
+    let build = || {
+        let mut pack = Pack::default();
+        pack.entries.push(PackEntry {
+            path: "mods/alpha-1.9.0.jar".to_string(),
+            contents: Some(fabric_jar("alpha", "1.9.0")),
+            required: true,
+            ..Default::default()
+        });
+        pack.overrides.common.insert(
+            "mods/alpha-1.10.0.jar".to_string(),
+            fabric_jar("alpha", "1.10.0"),
+        );
+        pack
+    };
+    let mut pack = build();
+    let report =
+        dedupe::dedupe(&mut pack, DuplicatePolicy::KeepNewest, &Default::default()).unwrap();
+    assert_eq!(report.groups[0].kept, "overrides/mods/alpha-1.10.0.jar");
+    assert_eq!(report.groups[0].matched_by, ["mod id alpha"]);
+    assert!(pack.entries.is_empty());
+
+    let mut pack = build();
+    dedupe::dedupe(
+        &mut pack,
+        DuplicatePolicy::KeepManifest,
+        &Default::default(),
+    )
+    .unwrap();
+    assert_eq!(pack.entries.len(), 1);
+    assert!(pack.overrides.common.is_empty());
+
+    let mut pack = build();
+    assert!(dedupe::dedupe(&mut pack, DuplicatePolicy::Error, &Default::default()).is_err());
+
+    use std::cmp::Ordering::*;
+    for (a, b, expected) in [
+        ("1.10.0", "1.9.0", Greater),
+        ("0.5.8+mc1.20.1", "0.5.11+mc1.20.1", Less),
+        ("2.0.0+mc1.20.1", "2.0.0+mc1.20.1", Equal),
+        ("1.0", "1.0.0", Less),
+        ("1.0.0-alpha", "1.0.0-beta", Less),
+    ] {
+        assert_eq!(compare_versions(a, b), expected, "{} {}", a, b);
+    }
+}
+
+/**
+ * Jar with a `fabric.mod.json` declaring `id`
+ */
+fn fabric_jar(id: &str, version: &str) -> Vec<u8> {
+    let metadata = serde_json::json!({ "schemaVersion": 1, "id": id, "version": version });
+    raw_zip(
+        &[("fabric.mod.json", metadata.to_string().as_bytes())],
+        CompressionMethod::Stored,
+    )
+}
+
+#[tokio::test]
+async fn deps_know_the_same_mod_across_platforms() {
+    let client = common::client();
+    // alpha comes from a modrinth index, beta from curseforge and requires curseforge project 100, alpha
+    let mut pack = packs::read_archive(
+        &mut ZipArchive::new(Cursor::new(common::pack("modrinth-external"))).unwrap(),
+    )
+    .unwrap();
+    pack.entries.push(PackEntry {
+        curse: Some(CurseRef {
+            project_id: 200,
+            file_id: 2000,
+        }),
+        required: true,
+        ..Default::default()
+    });
+    pack.entries.push(PackEntry {
+        path: "mods/alpha-fork.jar".to_string(),
+        contents: Some(fabric_jar("alpha", "1.0.1")),
+        required: true,
+        ..Default::default()
+    });
+    pack.resolve_curse(&client).await.unwrap();
+    pack.resolve_modrinth(&client).await.unwrap();
+    deps::collect(&mut pack, &client).await.unwrap();
+    assert_eq!(pack.entries[0].curse.as_ref().unwrap().project_id, 100);
+
+    let report = deps::check(&pack);
+    assert!(report.missing.is_empty(), "{}", report);
+    assert_eq!(report.duplicates.len(), 1, "{}", report);
+    assert_eq!(report.duplicates[0].entry.path, "mods/alpha-1.0.0.jar");
+    assert_eq!(report.duplicates[0].other.path, "mods/alpha-fork.jar");
+}
+
+#[test]
+fn entry_source_goes_by_the_download_host() {
+    let source = |url: &str| {
+        let entry = PackEntry {
+            downloads: vec![url.to_string()],
+            ..Default::default()
+        };
+        EntrySource::of(&entry, &OutputFormat::CURSEFORGE)
+    };
+    assert_eq!(
+        source("https://cdn.modrinth.com/data/a/versions/b/a.jar"),
+        EntrySource::Modrinth
+    );
+    assert_eq!(
+        source("https://edge.forgecdn.net/files/1/2/a.jar"),
+        EntrySource::Curseforge
+    );
+    assert_eq!(
+        source("https://example.com/cdn.modrinth.com/a.jar"),
+        EntrySource::Missing
+    );
+    assert_eq!(
+        source("https://notforgecdn.net/files/a.jar"),
+        EntrySource::Missing
+    );
+}
+
+#[tokio::test]
+async fn launcher_instances_keep_platform_ids() {
+    let client = common::client();
+    let mut pack = downloaded("curse-open").await;
+    pack.entries.push(PackEntry {
+        path: "mods/delta-1.0.0.jar".to_string(),
+        contents: Some(fabric_jar("delta", "1.0.0")),
+        modrinth: Some(ModrinthRef {
+            project_id: "DeltaProj".to_string(),
+            version_id: "DeltaV100".to_string(),
+        }),
+        required: true,
+        ..Default::default()
+    });
+    let ids = |pack: &Pack| {
+        pack.entries
+            .iter()
+            .map(|e| (e.path.clone(), e.curse.clone(), e.modrinth.clone()))
+            .collect::<Vec<_>>()
+    };
+    for format in [OutputFormat::ATLAUNCHER, OutputFormat::GDLAUNCHER] {
+        let mut output = Cursor::new(Vec::new());
+        write_pack(
+            &mut pack,
+            &format,
+            &mut output,
+            &Compression::default(),
+            DownloadPolicy::default(),
+            &client,
+        )
+        .await
+        .unwrap();
+        let read =
+            packs::read_archive(&mut ZipArchive::new(Cursor::new(output.into_inner())).unwrap())
+                .unwrap();
+        assert_eq!(ids(&read), ids(&pack), "{:?}", format);
+        assert_eq!(read.overrides.common["config/test.txt"], b"option=true\n");
+    }
+}
+
+#[test]
+fn diff_reports_added_removed_and_changed_entries() {
+    let entry = |path: &str, sha1: &str, curse: Option<u32>| PackEntry {
+        path: path.to_string(),
+        sha1: Some(sha1.to_string()),
+        curse: curse.map(|project_id| CurseRef {
+            project_id,
+            file_id: 1,
+        }),
+        ..Default::default()
+    };
+    let mut old = Pack {
+        minecraft: "1.20.1".to_string(),
+        entries: vec![
+            entry("mods/alpha-1.0.0.jar", "a1", Some(100)),
+            entry("mods/beta-2.0.0.jar", "b2", Some(200)),
+            entry("mods/same.jar", "s", None),
+        ],
+        ..Default::default()
+    };
+    old.loaders
+        .insert("fabric-loader".to_string(), "0.15.0".to_string());
+    old.overrides
+        .common
+        .insert("config/kept.txt".to_string(), b"a".to_vec());
+    old.overrides
+        .common
+        .insert("config/edited.txt".to_string(), b"a".to_vec());
+    old.overrides
+        .client
+        .insert("options.txt".to_string(), b"a".to_vec());
+    let mut new = Pack {
+        minecraft: "1.20.4".to_string(),
+        entries: vec![
+            entry("mods/same.jar", "s", None),
+            entry("mods/alpha-1.1.0.jar", "a2", Some(100)),
+            entry("mods/gamma-1.0.jar", "g1", Some(300)),
+        ],
+        ..Default::default()
+    };
+    new.loaders
+        .insert("fabric-loader".to_string(), "0.15.0".to_string());
+    new.overrides
+        .common
+        .insert("config/kept.txt".to_string(), b"a".to_vec());
+    new.overrides
+        .common
+        .insert("config/edited.txt".to_string(), b"b".to_vec());
+    new.overrides
+        .server
+        .insert("server.properties".to_string(), b"a".to_vec());
+
+    let report = diff::diff(&old, &new);
+    let paths =
+        |entries: &[diff::EntrySummary]| entries.iter().map(|e| e.path.clone()).collect::<Vec<_>>();
+    assert_eq!(paths(&report.added), ["mods/gamma-1.0.jar"]);
+    assert_eq!(paths(&report.removed), ["mods/beta-2.0.0.jar"]);
+    assert_eq!(report.upgraded.len(), 1);
+    assert_eq!(report.upgraded[0].from.path, "mods/alpha-1.0.0.jar");
+    assert_eq!(report.upgraded[0].to.path, "mods/alpha-1.1.0.jar");
+    assert_eq!(
+        report.minecraft.as_ref().and_then(|c| c.to.as_deref()),
+        Some("1.20.4")
+    );
+    assert!(report.loaders.is_empty());
+    assert_eq!(
+        report.overrides.added,
+        ["server-overrides/server.properties"]
+    );
+    assert_eq!(report.overrides.removed, ["client-overrides/options.txt"]);
+    assert_eq!(report.overrides.changed, ["overrides/config/edited.txt"]);
+    assert!(diff::diff(&new, &new).is_empty());
+}
+
+#[tokio::test]
+async fn port_reports_ported_and_missing_entries() {
+    let client = common::client();
+    let mut pack = Pack {
+        minecraft: "1.19.2".to_string(),
+        entries: vec![
+            PackEntry {
+                path: "mods/beta-1.0.0.jar".to_string(),
+                modrinth: Some(ModrinthRef {
+                    project_id: "BetaProj".to_string(),
+                    version_id: "BetaV100".to_string(),
+                }),
+                ..Default::default()
+            },
+            PackEntry {
+                path: "mods/alpha-0.9.0.jar".to_string(),
+                curse: Some(CurseRef {
+                    project_id: 100,
+                    file_id: 900,
+                }),
+                ..Default::default()
+            },
+            PackEntry {
+                path: "mods/unknown.jar".to_string(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    pack.loaders
+        .insert("fabric-loader".to_string(), "0.15.0".to_string());
+    let target = PortTarget::new(&pack, "1.20.1", None, None).unwrap();
+    let report = port::port(&pack, &client, target, ReleaseType::Release)
+        .await
+        .unwrap();
+    let ported: Vec<_> = report.ported.iter().map(|u| u.index).collect();
+    assert_eq!(ported, [0, 1]);
+    assert_eq!(report.ported[0].latest.path, "mods/beta-2.0.0.jar");
+    assert_eq!(report.missing.len(), 1);
+    assert_eq!(report.missing[0].path, "mods/unknown.jar");
+
+    port::apply(&mut pack, &report);
+    assert_eq!(pack.minecraft, "1.20.1");
+    assert_eq!(pack.entries.len(), 2);
+    assert_eq!(
+        pack.entries[0].modrinth.as_ref().unwrap().version_id,
+        "BetaV200"
+    );
+    assert_eq!(pack.entries[1].curse.as_ref().unwrap().file_id, 1000);
+}
+
+#[test]
+fn jar_inspect_reads_fabric_and_forge_metadata() {
+    let fabric = serde_json::json!({
+        "schemaVersion": 1,
+        "id": "alpha",
+        "version": "1.0.0",
+        "name": "Alpha",
+        "environment": "client",
+        "depends": { "fabricloader": ">=0.15", "minecraft": "1.20.1" },
+        "breaks": { "beta": "*" }
+    });
+    let mods = jar::inspect(&raw_zip(
+        &[("fabric.mod.json", fabric.to_string().as_bytes())],
+        CompressionMethod::Deflated,
+    ))
+    .unwrap();
+    assert_eq!(mods.len(), 1);
+    let alpha = &mods[0];
+    assert_eq!(
+        (alpha.mod_id.as_str(), alpha.loader.as_str()),
+        ("alpha", "fabric-loader")
+    );
+    assert_eq!(alpha.name.as_deref(), Some("Alpha"));
+    assert_eq!(alpha.version.as_deref(), Some("1.0.0"));
+    let env = alpha.env.as_ref().unwrap();
+    assert_eq!(
+        (env.client.as_str(), env.server.as_str()),
+        ("required", "unsupported")
+    );
+    let deps: Vec<_> = alpha
+        .dependencies
+        .iter()
+        .map(|d| (d.mod_id.as_str(), d.kind, d.version.as_deref()))
+        .collect();
+    assert_eq!(
+        deps,
+        [
+            ("fabricloader", DependencyKind::Required, Some(">=0.15")),
+            ("minecraft", DependencyKind::Required, Some("1.20.1")),
+            ("beta", DependencyKind::Incompatible, Some("*")),
+        ]
+    );
+
+    let mods_toml = r#"
+modLoader = "javafml"
+loaderVersion = "[47,)"
+license = "MIT"
+
+[[mods]]
+modId = "gamma"
+version = "${file.jarVersion}"
+displayName = "Gamma"
+
+[[dependencies.gamma]]
+modId = "forge"
+mandatory = true
+versionRange = "[47,)"
+ordering = "NONE"
+side = "BOTH"
+
+[[dependencies.gamma]]
+modId = "jei"
+mandatory = false
+versionRange = "*"
+ordering = "NONE"
+side = "CLIENT"
+"#;
+    let mods = jar::inspect(&raw_zip(
+        &[
+            (
+                "META-INF/MANIFEST.MF",
+                b"Manifest-Version: 1.0\r\nImplementation-Version: 3.2.1\r\n",
+            ),
+            ("META-INF/mods.toml", mods_toml.as_bytes()),
+        ],
+        CompressionMethod::Deflated,
+    ))
+    .unwrap();
+    assert_eq!(mods.len(), 1);
+    let gamma = &mods[0];
+    assert_eq!(
+        (gamma.mod_id.as_str(), gamma.loader.as_str()),
+        ("gamma", "forge")
+    );
+    assert_eq!(gamma.version.as_deref(), Some("3.2.1"));
+    assert!(gamma.env.is_none());
+    let deps: Vec<_> = gamma
+        .dependencies
+        .iter()
+        .map(|d| (d.mod_id.as_str(), d.kind))
+        .collect();
+    assert_eq!(
+        deps,
+        [
+            ("forge", DependencyKind::Required),
+            ("jei", DependencyKind::Optional)
+        ]
+    );
+
+    assert!(
+        jar::inspect(&raw_zip(
+            &[("readme.txt", b"hi")],
+            CompressionMethod::Stored
+        ))
+        .unwrap()
+        .is_empty()
+    );
+}
+
+#[test]
+fn modrinth_env_optional_on_one_side_only() {
+    let file = |name: &str, client: &str, server: &str| {
+        serde_json::json!({
+            "path": format!("mods/{}.jar", name),
+            "hashes": { "sha1": "1".repeat(40), "sha512": "5".repeat(128) },
+            "env": { "client": client, "server": server },
+            "downloads": [format!("https://cdn.modrinth.com/data/P{0}/versions/V{0}/{0}.jar", name)],
+            "fileSize": 1
+        })
+    };
+    let index = serde_json::json!({
+        "formatVersion": 1,
+        "game": "minecraft",
+        "versionId": "1.0.0",
+        "name": "Env Pack",
+        "files": [
+            file("client-needs", "required", "optional"),
+            file("everywhere-optional", "optional", "optional"),
+            file("client-only", "optional", "unsupported"),
+        ],
+        "dependencies": { "minecraft": "1.20.1", "fabric-loader": "0.15.0" }
+    });
+    let data = raw_zip(
+        &[("modrinth.index.json", index.to_string().as_bytes())],
+        CompressionMethod::Deflated,
+    );
+    let pack = packs::read_archive(&mut ZipArchive::new(Cursor::new(data)).unwrap()).unwrap();
+    let required: Vec<_> = pack.entries.iter().map(|e| e.required).collect();
+    assert_eq!(required, [true, false, false]);
+    let installed = |side: Side| {
+        pack.entries
+            .iter()
+            .filter(|e| install::selected(e, side, &OptionalMods::None))
+            .map(|e| e.path.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(installed(Side::Client), ["mods/client-needs.jar"]);
+    assert!(installed(Side::Server).is_empty());
+
+    let mut out = ZipWriter::new(Cursor::new(Vec::new()));
+    modrinth_pack::write(
+        &pack,
+        &mut out,
+        &Compression::default(),
+        DownloadPolicy::Embed,
+    )
+    .unwrap();
+    let files = common::unzip(&out.finish().unwrap().into_inner());
+    let written: Value = serde_json::from_slice(&files["modrinth.index.json"]).unwrap();
+    for (written, original) in written["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(index["files"].as_array().unwrap())
+    {
+        assert_eq!(written["env"], original["env"]);
+    }
+}
+
+#[test]
+fn modrinth_index_validation() {
+    let original: Value = serde_json::from_slice(
+        &fs::read(common::fixtures().join("golden/modrinth/modrinth.index.json")).unwrap(),
+    )
+    .unwrap();
+    let meta = serde_json::from_value(original.clone()).unwrap();
+    assert_eq!(modrinth_pack::validate(&meta), Vec::<String>::new());
+
+    let mut broken = original;
+    broken["dependencies"]["fabric"] = "0.15.11".into();
+    broken["dependencies"]["minecraft"] = "".into();
+    let sodium = &mut broken["files"][0];
+    sodium["hashes"]["sha1"] = "not a hash".into();
+    sodium["downloads"][0] = "https://evil.example/cdn.modrinth.com/sodium.jar".into();
+    broken["files"][1]["path"] = "../resourcepacks/FreshAnimations_v1.9.zip".into();
+    let meta = serde_json::from_value(broken).unwrap();
+    assert_eq!(
+        modrinth_pack::validate(&meta),
+        [
+            "dependencies has no minecraft version",
+            "dependencies has unknown key fabric",
+            "mods/sodium-fabric-0.5.11+mc1.20.1.jar has no valid sha1",
+            "mods/sodium-fabric-0.5.11+mc1.20.1.jar downloads from \
+             https://evil.example/cdn.modrinth.com/sodium.jar, which is not an allowed domain",
+            "../resourcepacks/FreshAnimations_v1.9.zip is not a safe relative path",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn validate_archive_checks_written_and_foreign_packs() {
+    let client = common::client();
+    let mut pack = downloaded("curse-open").await;
+    let mut instance = Cursor::new(Vec::new());
+    write_pack(
+        &mut pack,
+        &OutputFormat::GDLAUNCHER,
+        &mut instance,
+        &Compression::default(),
+        DownloadPolicy::default(),
+        &client,
+    )
+    .await
+    .unwrap();
+    let mut pack =
+        packs::read_archive(&mut ZipArchive::new(Cursor::new(instance.into_inner())).unwrap())
+            .unwrap();
+    // an instance exported by gdlauncher itself has no sidecar and so no pack name
+    pack.name.clear();
+    let mut output = Cursor::new(Vec::new());
+    write_pack(
+        &mut pack,
+        &OutputFormat::MODRINTH,
+        &mut output,
+        &Compression::default(),
+        DownloadPolicy::default(),
+        &client,
+    )
+    .await
+    .unwrap();
+    let problems = modrinth_pack::validate_archive(
+        &mut ZipArchive::new(Cursor::new(output.into_inner())).unwrap(),
+    )
+    .unwrap();
+    assert!(problems.is_empty(), "{:?}", problems);
+
+    let index = serde_json::json!({
+        "formatVersion": 1,
+        "game": "minecraft",
+        "versionId": "1.0.0",
+        "name": "Foreign",
+        "files": [{
+            "path": "/etc/alpha.jar",
+            "hashes": { "sha1": "1".repeat(40), "sha512": "5".repeat(128) },
+            "downloads": ["https://cdn.modrinth.com/data/A/versions/B/alpha.jar"],
+            "fileSize": 1
+        }],
+        "dependencies": { "fabric-loader": "0.15.0" }
+    });
+    let data = raw_zip(
+        &[("modrinth.index.json", index.to_string().as_bytes())],
+        CompressionMethod::Deflated,
+    );
+    let problems =
+        modrinth_pack::validate_archive(&mut ZipArchive::new(Cursor::new(data)).unwrap()).unwrap();
+    assert_eq!(
+        problems,
+        [
+            "dependencies has no minecraft version",
+            "/etc/alpha.jar is not a safe relative path",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn compression_stores_archives_and_deflates_the_rest() {
+    let client = common::client();
+    let write = |compression: Compression| {
+        let client = client.clone();
+        async move {
+            let mut pack = downloaded("curse-open").await;
+            let mut output = Cursor::new(Vec::new());
+            write_pack(
+                &mut pack,
+                &OutputFormat::ATLAUNCHER,
+                &mut output,
+                &compression,
+                DownloadPolicy::default(),
+                &client,
+            )
+            .await
+            .map(|_| output.into_inner())
+        }
+    };
+    let methods = |data: Vec<u8>| {
+        let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+        ["mods/alpha-1.0.0.jar", "config/test.txt"]
+            .map(|name| archive.by_name(name).unwrap().compression())
+    };
+
+    let output = write(Compression::default()).await.unwrap();
+    assert_eq!(
+        methods(output),
+        [CompressionMethod::Stored, CompressionMethod::Deflated]
+    );
+
+    // --compress-jars
+    let output = write(Compression {
+        store_archives: false,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        methods(output),
+        [CompressionMethod::Deflated, CompressionMethod::Deflated]
+    );
+
+    let too_high = Compression {
+        level: Some(99),
+        ..Default::default()
+    };
+    let mut pack = downloaded("curse-open").await;
+    let mut output = Cursor::new(Vec::new());
+    let err = write_pack(
+        &mut pack,
+        &OutputFormat::ATLAUNCHER,
+        &mut output,
+        &too_high,
+        DownloadPolicy::default(),
+        &client,
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("level 99 is outside 0..=9"),
+        "{:#}",
+        err
+    );
+    assert!(output.into_inner().is_empty());
+    assert!(
+        Compression {
+            method: packs::CompressionMethod::Store,
+            level: Some(99),
+            ..Default::default()
+        }
+        .check()
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn progress_goes_through_every_phase_in_order() {
+    let (sender, mut receiver) = progress::channel();
+    let mut opts = ConvertOptions::new(OutputFormat::ATLAUNCHER, common::client());
+    opts.progress = Some(sender);
+    convert(
+        Cursor::new(common::pack("curse-open")),
+        Cursor::new(Vec::new()),
+        &opts,
+    )
+    .await
+    .unwrap();
+    let mut timer = PhaseTimer::default();
+    let mut phases: Vec<String> = Vec::new();
+    while let Ok(event) = receiver.try_recv() {
+        timer.observe(&event);
+        if let Progress::Downloading {
+            file, files, bytes, ..
+        } = &event
+        {
+            assert!(file < files && *bytes > 0, "{:?}", event);
+        }
+        if phases.last() != Some(&event.phase()) {
+            phases.push(event.phase());
+        }
+    }
+    assert_eq!(
+        phases,
+        [
+            "reading",
+            "resolving curseforge",
+            "downloading",
+            "writing",
+            "done"
+        ]
+    );
+    let timed: Vec<_> = timer.finish().into_iter().map(|t| t.phase).collect();
+    assert_eq!(timed, phases[..phases.len() - 1]);
+}
+
+#[tokio::test]
+async fn rebuilds_are_byte_identical() {
+    let client = common::client();
+    let overrides = [
+        ("config/a.txt", "a"),
+        ("config/b.txt", "b"),
+        ("options.txt", "c"),
+        ("mods/extra.jar", "d"),
+    ];
+    let build = |reversed: bool| {
+        let client = client.clone();
+        async move {
+            let mut pack = downloaded("curse-open").await;
+            let mut files = overrides.to_vec();
+            if reversed {
+                files.reverse();
+            }
+            for (path, data) in files {
+                pack.overrides
+                    .client
+                    .insert(path.to_string(), data.as_bytes().to_vec());
+            }
+            let mut output = Cursor::new(Vec::new());
+            write_pack(
+                &mut pack,
+                &OutputFormat::MODRINTH,
+                &mut output,
+                &Compression::default(),
+                DownloadPolicy::default(),
+                &client,
+            )
+            .await
+            .unwrap();
+            output.into_inner()
+        }
+    };
+    let first = build(false).await;
+    assert!(first == build(true).await);
+    let mut archive = ZipArchive::new(Cursor::new(first)).unwrap();
+    for index in 0..archive.len() {
+        let file = archive.by_index(index).unwrap();
+        assert_eq!(
+            file.last_modified(),
+            Some(packs::archive_time()),
+            "{}",
+            file.name()
+        );
+    }
+
+    let time = |y, mo, d, h, mi, s| DateTime::from_date_and_time(y, mo, d, h, mi, s).ok();
+    assert_eq!(
+        packs::epoch_to_zip_time("1700000000"),
+        time(2023, 11, 14, 22, 13, 20)
+    );
+    assert_eq!(
+        packs::epoch_to_zip_time("315532800"),
+        time(1980, 1, 1, 0, 0, 0)
+    );
+    assert_eq!(packs::epoch_to_zip_time("0"), None);
+    assert_eq!(packs::epoch_to_zip_time("soon"), None);
+}
@@ -0,0 +1,311 @@
+use std::{fs, path::Path};
+
+use multipack::platforms::{
+    curse::{
+        CurseModLoaderType, FileLookupResponse, FileStatus, FingerprintResponse, HashAlgo,
+        ModLookupResponse, ModStatus, PackMeta as CursePackMeta, RelationType, ReleaseType,
+    },
+    mr::{PackMeta as ModrinthPackMeta, ProjectResponse, VersionFileResponse},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+fn fixture(path: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+fn parse<T: DeserializeOwned>(path: &str) -> T {
+    serde_json::from_str(&fixture(path)).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+/**
+ * Every `.json` below `tests/fixtures/<dir>` whose path relative to it matches `filter`
+ */
+fn corpus(dir: &str, filter: impl Fn(&str) -> bool) -> Vec<String> {
+    fn walk(root: &Path, dir: &Path, out: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(root, &path, out);
+            } else if path.extension().is_some_and(|e| e == "json") {
+                let relative = path.strip_prefix(root).unwrap().to_string_lossy();
+                out.push(relative.replace('\\', "/"));
+            }
+        }
+    }
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut out = Vec::new();
+    walk(&root, &root.join(dir), &mut out);
+    out.retain(|p| filter(p));
+    out.sort();
+    assert!(!out.is_empty(), "no fixtures in {}", dir);
+    out
+}
+
+#[test]
+fn curse_mod_lookup() {
+    let data = parse::<ModLookupResponse>("golden/curseforge/mod_lookup.json").data;
+    assert_eq!(data.id, 238222);
+    assert_eq!(data.status, ModStatus::Approved);
+    assert_eq!(data.class_id, Some(6));
+    assert_eq!(data.thumbs_up_count, 1205);
+    assert_eq!(data.rating, None);
+    assert_eq!(data.allow_mod_distribution, Some(true));
+    assert_eq!(data.links.wiki_url.as_deref(), Some(""));
+    assert_eq!(data.categories[1].is_class, Some(true));
+    assert_eq!(data.categories[1].class_id, None);
+    assert!(data.logo.is_some());
+    assert_eq!(data.screenshots.len(), 1);
+    assert_eq!(data.latest_files[0].id, data.main_file_id);
+    let loaders: Vec<_> = data
+        .latest_file_indexes
+        .iter()
+        .map(|i| (i.release_type, i.mod_loader.clone()))
+        .collect();
+    assert_eq!(
+        loaders,
+        [
+            (ReleaseType::Release, Some(CurseModLoaderType::Forge)),
+            (ReleaseType::Release, Some(CurseModLoaderType::Fabric)),
+            (ReleaseType::Beta, None),
+            (ReleaseType::Alpha, Some(CurseModLoaderType::NeoForge)),
+        ]
+    );
+}
+
+#[test]
+fn curse_file_lookup() {
+    let file = parse::<FileLookupResponse>("golden/curseforge/file_lookup.json").data;
+    assert_eq!(file.file_name, "jei-1.20.1-forge-15.3.0.4.jar");
+    assert_eq!(file.file_status, FileStatus::Approved);
+    assert_eq!(file.hashes[0].algo, HashAlgo::Sha1);
+    assert_eq!(file.hashes[1].algo, HashAlgo::Md5);
+    assert_eq!(file.file_fingerprint, 3227461530);
+    assert_eq!(file.modules.len(), 3);
+    assert!(file.download_url.is_some());
+    assert_eq!(file.sortable_game_versions[1].game_version, "1.20.1");
+}
+
+#[test]
+fn curse_restricted_file_lookup() {
+    let file = parse::<FileLookupResponse>("golden/curseforge/file_lookup_restricted.json").data;
+    assert_eq!(file.download_url, None);
+    assert_eq!(file.file_size_on_disk, None);
+    assert_eq!(file.alternate_file_id, None);
+    assert_eq!(file.release_type, ReleaseType::Alpha);
+    assert_eq!(file.file_status, FileStatus::Released);
+    assert_eq!(file.sortable_game_versions[2].game_version_type_id, None);
+    let relations: Vec<_> = file
+        .dependencies
+        .iter()
+        .map(|d| d.relation_type.clone())
+        .collect();
+    assert_eq!(
+        relations,
+        [
+            RelationType::EmbeddedLibrary,
+            RelationType::OptionalDependency,
+            RelationType::RequiredDependency,
+            RelationType::Tool,
+            RelationType::Incompatible,
+            RelationType::Include,
+        ]
+    );
+}
+
+#[test]
+fn curse_fingerprint_lookup() {
+    let data = parse::<FingerprintResponse>("golden/curseforge/fingerprints.json").data;
+    assert!(data.is_cache_built);
+    assert_eq!(data.exact_matches.len(), 1);
+    let found = &data.exact_matches[0];
+    assert_eq!(found.id, 238222);
+    assert_eq!(found.file.file_fingerprint, 3227461530);
+    assert_eq!(found.latest_files[0].id, found.file.id);
+    assert_eq!(data.exact_fingerprints, [3227461530]);
+    assert_eq!(data.installed_fingerprints.len(), 2);
+    assert_eq!(data.unmatched_fingerprints, [1529498042]);
+}
+
+#[test]
+fn curse_manifests() {
+    let forge = parse::<CursePackMeta>("golden/curseforge/manifest_forge.json");
+    assert_eq!(forge.manifest_type, "minecraftModpack");
+    assert_eq!(forge.manifest_version, 1);
+    assert_eq!(forge.minecraft.mod_loaders[0].id, "forge-47.2.0");
+    assert!(forge.minecraft.mod_loaders[0].primary);
+    assert!(!forge.files[1].required);
+    let original: Value =
+        serde_json::from_str(&fixture("golden/curseforge/manifest_forge.json")).unwrap();
+    assert_eq!(serde_json::to_value(&forge).unwrap(), original);
+    let neoforge = parse::<CursePackMeta>("golden/curseforge/manifest_neoforge.json");
+    assert!(neoforge.files.is_empty());
+    assert_eq!(neoforge.overrides, "overrides");
+}
+
+#[test]
+fn modrinth_version_file() {
+    let version = parse::<VersionFileResponse>("golden/modrinth/version_file.json");
+    assert_eq!(version.project_id, "AANobbMI");
+    assert_eq!(version.files.len(), 2);
+    assert!(version.files[0].primary);
+    assert_eq!(version.files[1].file_type.as_deref(), Some("sources-jar"));
+    let kinds: Vec<_> = version
+        .dependencies
+        .iter()
+        .map(|d| d.dependency_type.as_str())
+        .collect();
+    assert_eq!(kinds, ["optional", "required", "embedded", "incompatible"]);
+    assert_eq!(version.dependencies[1].project_id, None);
+    let versions = parse::<Vec<VersionFileResponse>>("golden/modrinth/project_versions.json");
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[1].version_type, "beta");
+}
+
+#[test]
+fn modrinth_project() {
+    let project = parse::<ProjectResponse>("golden/modrinth/project.json");
+    assert_eq!(project.id, "AANobbMI");
+    assert_eq!(project.slug, "sodium");
+    assert_eq!(project.title, "Sodium");
+    assert_eq!(project.project_type, "mod");
+    assert_eq!(project.loaders, ["fabric", "quilt"]);
+}
+
+#[test]
+fn modrinth_index_round_trips() {
+    let original: Value =
+        serde_json::from_str(&fixture("golden/modrinth/modrinth.index.json")).unwrap();
+    let meta: ModrinthPackMeta = serde_json::from_value(original.clone()).unwrap();
+    assert_eq!(meta.files[1].downloads.len(), 2);
+    assert_eq!(serde_json::to_value(&meta).unwrap(), original);
+}
+
+#[test]
+fn modrinth_index_optional_fields() {
+    let meta = parse::<ModrinthPackMeta>("golden/modrinth/modrinth.index.minimal.json");
+    assert_eq!(meta.summary, "");
+    assert!(meta.files[0].env.is_none());
+    let written = serde_json::to_value(&meta).unwrap();
+    assert!(written["files"][0].get("env").is_none());
+}
+
+#[test]
+fn mock_api_fixtures_match_the_structs() {
+    for path in corpus("api/v1/mods", |p| !p.contains("/files/")) {
+        parse::<ModLookupResponse>(&path);
+    }
+    for path in corpus("api/v1/mods", |p| p.contains("/files/")) {
+        parse::<FileLookupResponse>(&path);
+    }
+    for path in corpus("api/v2", |p| !p.contains("/project/")) {
+        parse::<VersionFileResponse>(&path);
+    }
+    for path in corpus("api/v2/project", |p| p.ends_with("/version.json")) {
+        parse::<Vec<VersionFileResponse>>(&path);
+    }
+    for path in corpus("api/v2/project", |p| !p.ends_with("/version.json")) {
+        parse::<ProjectResponse>(&path);
+    }
+    for path in corpus("api/v1", |p| p.ends_with("/fingerprints.json")) {
+        parse::<FingerprintResponse>(&path);
+    }
+    for path in corpus("packs", |p| p.ends_with("manifest.json")) {
+        parse::<CursePackMeta>(&path);
+    }
+    for path in corpus("packs", |p| p.ends_with("modrinth.index.json")) {
+        parse::<ModrinthPackMeta>(&path);
+    }
+}
+
+/**
+ * Checks every number in `values` reads as its variant and writes back as the number, and that the first number past
+ * them reads as `unknown` and writes back unchanged
+ */
+fn enum_values<T>(values: &[(u32, T)], unknown: impl Fn(u32) -> T)
+where
+    T: DeserializeOwned + Serialize + PartialEq + std::fmt::Debug,
+{
+    for (number, expected) in values {
+        assert_eq!(
+            &serde_json::from_str::<T>(&number.to_string()).unwrap(),
+            expected
+        );
+        assert_eq!(serde_json::to_string(expected).unwrap(), number.to_string());
+    }
+    let past = values.iter().map(|(n, _)| n).max().unwrap() + 1;
+    let parsed = serde_json::from_str::<T>(&past.to_string()).unwrap();
+    assert_eq!(parsed, unknown(past));
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), past.to_string());
+    assert!(serde_json::from_str::<T>("\"1\"").is_err());
+}
+
+#[test]
+fn curse_enums() {
+    enum_values(
+        &[
+            (1, ModStatus::New),
+            (4, ModStatus::Approved),
+            (10, ModStatus::UnderReview),
+        ],
+        ModStatus::Unknown,
+    );
+    enum_values(
+        &[
+            (1, FileStatus::Processing),
+            (4, FileStatus::Approved),
+            (10, FileStatus::Released),
+            (23, FileStatus::PostProcessing),
+        ],
+        FileStatus::Unknown,
+    );
+    enum_values(
+        &[
+            (1, RelationType::EmbeddedLibrary),
+            (3, RelationType::RequiredDependency),
+            (6, RelationType::Include),
+        ],
+        RelationType::Unknown,
+    );
+    enum_values(
+        &[(1, HashAlgo::Sha1), (2, HashAlgo::Md5)],
+        HashAlgo::Unknown,
+    );
+    enum_values(
+        &[
+            (1, ReleaseType::Release),
+            (2, ReleaseType::Beta),
+            (3, ReleaseType::Alpha),
+        ],
+        ReleaseType::Unknown,
+    );
+    enum_values(
+        &[
+            (0, CurseModLoaderType::Any),
+            (1, CurseModLoaderType::Forge),
+            (4, CurseModLoaderType::Fabric),
+            (5, CurseModLoaderType::Quilt),
+            (6, CurseModLoaderType::NeoForge),
+        ],
+        CurseModLoaderType::Unknown,
+    );
+}
+
+#[test]
+fn curse_file_with_new_enum_values_still_parses() {
+    let mut response: Value =
+        serde_json::from_str(&fixture("golden/curseforge/file_lookup_restricted.json")).unwrap();
+    let file = &mut response["data"];
+    file["fileStatus"] = 24.into();
+    file["releaseType"] = 4.into();
+    file["hashes"][0]["algo"] = 3.into();
+    file["dependencies"][0]["relationType"] = 7.into();
+    let file = serde_json::from_value::<FileLookupResponse>(response)
+        .unwrap()
+        .data;
+    assert_eq!(file.file_status, FileStatus::Unknown(24));
+    assert_eq!(file.release_type, ReleaseType::Unknown(4));
+    assert_eq!(file.hashes[0].algo, HashAlgo::Unknown(3));
+    assert_eq!(file.dependencies[0].relation_type, RelationType::Unknown(7));
+}